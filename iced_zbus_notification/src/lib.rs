@@ -15,6 +15,7 @@ use zbus::{interface, object_server::SignalEmitter, zvariant::OwnedValue};
 
 use futures::channel::mpsc::Sender;
 use std::sync::{Arc, LazyLock, RwLock};
+use std::time::SystemTime;
 use zbus::connection;
 
 use zbus::zvariant::Type;
@@ -128,9 +129,12 @@ pub struct NotifyHint {
     image_data: Option<ImageData>,
     desktop_entry: Option<String>,
     urgency: Urgency,
+    category: Option<String>,
+    /// the hints not handled by the daemon, formatted as text
+    others: Vec<(String, String)>,
 }
 
-#[derive(Deserialize, Serialize, Type, Debug, Clone, OwnedValue, PartialEq)]
+#[derive(Deserialize, Serialize, Type, Debug, Clone, Copy, OwnedValue, PartialEq, Eq)]
 #[repr(u8)]
 pub enum Urgency {
     Low = 0,
@@ -168,6 +172,31 @@ fn keep_rgba(has_alpha: bool, data: &[u8]) -> Vec<u8> {
 }
 
 impl NotifyHint {
+    /// The hint of a notification made by the daemon itself
+    pub fn new(urgency: Urgency, category: Option<String>) -> Self {
+        Self {
+            image_data: None,
+            desktop_entry: None,
+            urgency,
+            category,
+            others: Vec::new(),
+        }
+    }
+
+    /// Use the rgba pixels as the image of the notification
+    pub fn with_rgba_image(mut self, width: i32, height: i32, pixels: Vec<u8>) -> Self {
+        self.image_data = Some(ImageData {
+            width,
+            height,
+            rowstride: width * 4,
+            has_alpha: true,
+            bits_per_sample: 8,
+            channels: 4,
+            data: pixels,
+        });
+        self
+    }
+
     fn desktop_image(&self) -> Option<ImageInfo> {
         self.desktop_entry
             .as_ref()
@@ -184,6 +213,30 @@ impl NotifyHint {
     pub fn is_critical(&self) -> bool {
         matches!(self.urgency, Urgency::Critical)
     }
+
+    pub fn urgency(&self) -> Urgency {
+        self.urgency
+    }
+
+    pub fn category(&self) -> Option<&str> {
+        self.category.as_deref()
+    }
+
+    pub fn desktop_entry(&self) -> Option<&str> {
+        self.desktop_entry.as_deref()
+    }
+
+    /// The width and height of the image-data hint
+    pub fn image_size(&self) -> Option<(i32, i32)> {
+        self.image_data
+            .as_ref()
+            .map(|data| (data.width, data.height))
+    }
+
+    /// Other hints sent with the notification, sorted by name
+    pub fn raw_hints(&self) -> &[(String, String)] {
+        &self.others
+    }
 }
 
 /// Describe the information in every time notify send
@@ -205,6 +258,8 @@ pub struct NotifyUnit {
     pub timeout: i32,
     /// other information like image-data
     pub hint: NotifyHint,
+    /// when the notification is received
+    pub timestamp: SystemTime,
 }
 
 impl NotifyUnit {
@@ -213,6 +268,16 @@ impl NotifyUnit {
         self.actions.contains(&"inline-reply".to_owned())
     }
 
+    /// Get the actions as `(key, label)` pairs
+    /// The default action and the inline-reply are not included, they are handled on their own
+    pub fn action_pairs(&self) -> Vec<(String, String)> {
+        self.actions
+            .chunks_exact(2)
+            .filter(|pair| pair[0] != DEFAULT_ACTION && pair[0] != "inline-reply")
+            .map(|pair| (pair[0].clone(), pair[1].clone()))
+            .collect()
+    }
+
     /// Get the image inside the unit
     /// It will use the image in hint first
     /// Then use icon from the param by notify
@@ -242,6 +307,10 @@ impl NotifyUnit {
     pub fn is_critical(&self) -> bool {
         self.hint.is_critical()
     }
+
+    pub fn urgency(&self) -> Urgency {
+        self.hint.urgency()
+    }
 }
 
 /// Set the server info in `get_server_information`
@@ -391,7 +460,15 @@ impl<T: From<NotifyMessage> + Send + 'static> LaLaMako<T> {
         let urgency = hints
             .remove("urgency")
             .and_then(|v| v.try_into().ok())
-            .unwrap_or(Urgency::Low);
+            .unwrap_or(Urgency::Normal);
+
+        let category: Option<String> = hints.remove("category").and_then(|v| v.try_into().ok());
+
+        let mut others: Vec<(String, String)> = hints
+            .iter()
+            .map(|(key, value)| (key.to_string(), (**value).to_string()))
+            .collect();
+        others.sort();
 
         self.sender
             .try_send(
@@ -407,7 +484,10 @@ impl<T: From<NotifyMessage> + Send + 'static> LaLaMako<T> {
                         image_data,
                         desktop_entry,
                         urgency,
+                        category,
+                        others,
                     },
+                    timestamp: SystemTime::now(),
                 }))
                 .into(),
             )
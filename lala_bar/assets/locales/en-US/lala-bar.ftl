@@ -9,6 +9,20 @@ error-notification-not-found = Error Happened, LaLa cannot find notification for
 balance-left = Left: { $percent }%
 balance-right = Right: { $percent }%
 balance-combined = Balance: { $percent }%
+channel-mono = Mono: { $percent }%
+channel-volume = { $channel }: { $percent }%
+mute = mute
+unmute = unmute
+mixer = mixer
+no-streams = no application is playing sound
+devices = devices
+no-devices = no audio device
+outputs = outputs
+inputs = inputs
+profiles = profiles
+mic = mic
+mic-muted = mic off
+capturing = capturing audio: { $apps }
 
 #labels
 clear-all = clear all
@@ -24,7 +38,58 @@ pick-time = Pick time
 
 #general-actions
 send = send
-inline-reply-placeholder = reply something
+inline-reply-placeholder = reply something, shift+enter for a new line
+sent-replies = sent replies
 launcher-placeholder = put the launcher name...
 # $count - Number of hidden notifications
 hidden-notifications = hidden notifications { $count }
+
+#notification details
+copy-summary = Copy summary
+copy-body = Copy body
+details = Details
+copy-all = copy all
+close = close
+detail-app-name = App name
+detail-id = ID
+detail-summary = Summary
+detail-body = Body
+detail-icon = Icon
+detail-actions = Actions
+detail-timeout = Timeout
+detail-received = Received
+detail-urgency = Urgency
+detail-category = Category
+detail-desktop-entry = Desktop entry
+detail-image-data = Image data
+
+#links
+open-link-confirm = Open this link?
+open-link = open
+always-allow-scheme = always allow { $scheme }
+cancel = cancel
+
+#media
+pin = pin
+unpin = unpin
+shuffle = shuffle
+loop-none = no loop
+loop-track = loop track
+loop-playlist = loop all
+player-volume = volume
+no-player = no player is running
+raise-player = show
+quit-player = quit
+other-players = other players
+player-degraded = partly connected
+player-retrying = connecting (attempt { $attempt })
+player-gone = cannot connect
+unavailable-players = unavailable players
+up-next = up next
+playlists = playlists
+pause = pause
+next = next
+history-week = played this week
+history-empty = nothing played yet
+history-export = export
+history-export-failed = cannot export the history
@@ -0,0 +1,241 @@
+use std::{
+    collections::VecDeque,
+    hash::{DefaultHasher, Hash, Hasher},
+    io::Cursor,
+    path::{Path, PathBuf},
+    sync::{LazyLock, Mutex},
+    time::{Duration, SystemTime},
+};
+
+use anyhow::{Context, bail};
+use iced::widget::image;
+
+/// The art is downscaled to fit in a square of this size
+const ART_SIZE: u32 = 512;
+
+/// The size limit of the art cache on disk
+const MAX_CACHE_BYTES: u64 = 50 * 1024 * 1024;
+
+/// Art which is larger than this is not downloaded
+const MAX_ART_BYTES: usize = 20 * 1024 * 1024;
+
+/// How many loaded arts are kept in memory
+const MEMORY_CACHE_COUNT: usize = 16;
+
+static HTTP_CLIENT: LazyLock<reqwest::Client> = LazyLock::new(|| {
+    reqwest::Client::builder()
+        .timeout(Duration::from_secs(10))
+        .build()
+        .unwrap_or_default()
+});
+
+static MEMORY_CACHE: LazyLock<Mutex<VecDeque<(String, image::Handle)>>> =
+    LazyLock::new(|| Mutex::new(VecDeque::new()));
+
+// NOTE: a local file can be rewritten under the same path, so it is read again every time
+fn memory_cacheable(url: &str) -> bool {
+    !url.starts_with("file:")
+}
+
+fn cached_in_memory(url: &str) -> Option<image::Handle> {
+    if !memory_cacheable(url) {
+        return None;
+    }
+    let cache = MEMORY_CACHE.lock().unwrap();
+    cache
+        .iter()
+        .find(|(cached, _)| cached == url)
+        .map(|(_, handle)| handle.clone())
+}
+
+fn cache_in_memory(url: &str, handle: image::Handle) {
+    if !memory_cacheable(url) {
+        return;
+    }
+    let mut cache = MEMORY_CACHE.lock().unwrap();
+    cache.retain(|(cached, _)| cached != url);
+    cache.push_front((url.to_owned(), handle));
+    cache.truncate(MEMORY_CACHE_COUNT);
+}
+
+// NOTE: the hash is only used to name the files in the cache, if it changes between builds
+// the art is just downloaded again
+fn cache_file_name(url: &str) -> String {
+    let mut hasher = DefaultHasher::new();
+    url.hash(&mut hasher);
+    format!("{:016x}.png", hasher.finish())
+}
+
+fn cache_dir() -> Option<PathBuf> {
+    xdg::BaseDirectories::with_prefix("lala-bar")
+        .create_cache_directory("art")
+        .ok()
+}
+
+/// Decode the image and downscale it to fit in ART_SIZE, the result is encoded as png
+fn downscale(data: &[u8]) -> anyhow::Result<Vec<u8>> {
+    let mut art = ::image::load_from_memory(data)?;
+    if art.width() > ART_SIZE || art.height() > ART_SIZE {
+        art = art.thumbnail(ART_SIZE, ART_SIZE);
+    }
+    let mut png = Vec::new();
+    art.write_to(&mut Cursor::new(&mut png), ::image::ImageFormat::Png)?;
+    Ok(png)
+}
+
+async fn downscale_in_background(data: Vec<u8>) -> anyhow::Result<Vec<u8>> {
+    tokio::task::spawn_blocking(move || downscale(&data)).await?
+}
+
+// NOTE: the oldest files are removed first, a file is touched every time it is read
+fn prune_cache(dir: &Path) -> std::io::Result<()> {
+    let mut files = Vec::new();
+    for entry in std::fs::read_dir(dir)? {
+        let entry = entry?;
+        let metadata = entry.metadata()?;
+        if metadata.is_file() {
+            let modified = metadata.modified().unwrap_or(SystemTime::UNIX_EPOCH);
+            files.push((modified, metadata.len(), entry.path()));
+        }
+    }
+    let mut total: u64 = files.iter().map(|(_, len, _)| len).sum();
+    files.sort_by_key(|(modified, _, _)| *modified);
+    for (_, len, path) in files {
+        if total <= MAX_CACHE_BYTES {
+            break;
+        }
+        std::fs::remove_file(path)?;
+        total -= len;
+    }
+    Ok(())
+}
+
+async fn load_remote(url: &str) -> anyhow::Result<Vec<u8>> {
+    let dir = cache_dir();
+    let cache_file = dir.as_ref().map(|dir| dir.join(cache_file_name(url)));
+    if let Some(cache_file) = &cache_file
+        && let Ok(data) = tokio::fs::read(cache_file).await
+    {
+        if let Ok(file) = std::fs::File::options().append(true).open(cache_file) {
+            file.set_modified(SystemTime::now()).ok();
+        }
+        return Ok(data);
+    }
+    let response = HTTP_CLIENT.get(url).send().await?.error_for_status()?;
+    if response
+        .content_length()
+        .is_some_and(|len| len as usize > MAX_ART_BYTES)
+    {
+        bail!("the art is too large");
+    }
+    let data = response.bytes().await?;
+    if data.len() > MAX_ART_BYTES {
+        bail!("the art is too large");
+    }
+    let png = downscale_in_background(data.to_vec()).await?;
+    // NOTE: the art is still shown if it cannot be cached, like on a full or read-only disk
+    if let (Some(dir), Some(cache_file)) = (dir, cache_file) {
+        if let Err(e) = tokio::fs::write(&cache_file, &png).await {
+            tracing::warn!("{url}: cannot cache the art: {e}");
+        } else if let Ok(Err(e)) = tokio::task::spawn_blocking(move || prune_cache(&dir)).await {
+            tracing::warn!("cannot prune the art cache: {e}");
+        }
+    }
+    Ok(png)
+}
+
+async fn load(url: &str) -> anyhow::Result<Vec<u8>> {
+    let parsed = url::Url::parse(url)?;
+    match parsed.scheme() {
+        "file" => {
+            let path = parsed
+                .to_file_path()
+                .map_err(|_| anyhow::anyhow!("not a local path"))?;
+            downscale_in_background(tokio::fs::read(path).await?).await
+        }
+        "http" | "https" => load_remote(url).await,
+        "data" => {
+            let data = data_url::DataUrl::process(url)
+                .map_err(|e| anyhow::anyhow!("{e:?}"))?
+                .decode_to_vec()
+                .map_err(|e| anyhow::anyhow!("{e:?}"))?
+                .0;
+            downscale_in_background(data).await
+        }
+        scheme => bail!("unsupported scheme {scheme}"),
+    }
+}
+
+/// Load the art from a file, http(s) or data url, None if it cannot be loaded
+pub async fn load_art(url: &str) -> Option<image::Handle> {
+    if let Some(handle) = cached_in_memory(url) {
+        return Some(handle);
+    }
+    match load(url).await.context("cannot load the art") {
+        Ok(png) => {
+            let handle = image::Handle::from_bytes(png);
+            cache_in_memory(url, handle.clone());
+            Some(handle)
+        }
+        Err(e) => {
+            tracing::warn!("{url}: {e:#}");
+            None
+        }
+    }
+}
+
+/// Decode the art into rgba pixels which fit in a square of the size
+pub async fn art_rgba(handle: image::Handle, size: u32) -> Option<(u32, u32, Vec<u8>)> {
+    tokio::task::spawn_blocking(move || {
+        let art = match handle {
+            image::Handle::Bytes(_, bytes) => ::image::load_from_memory(&bytes).ok()?,
+            image::Handle::Path(_, path) => ::image::open(path).ok()?,
+            image::Handle::Rgba {
+                width,
+                height,
+                pixels,
+                ..
+            } => ::image::RgbaImage::from_raw(width, height, pixels.to_vec())?.into(),
+        };
+        let art = art.thumbnail(size, size).into_rgba8();
+        Some((art.width(), art.height(), art.into_raw()))
+    })
+    .await
+    .ok()?
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn downscale_large_art() {
+        let art = ::image::RgbImage::new(1024, 256);
+        let mut png = Vec::new();
+        art.write_to(&mut Cursor::new(&mut png), ::image::ImageFormat::Png)
+            .unwrap();
+        let small = ::image::load_from_memory(&downscale(&png).unwrap()).unwrap();
+        assert_eq!((small.width(), small.height()), (512, 128));
+    }
+
+    #[test]
+    fn local_art_is_not_kept_in_memory() {
+        let handle = image::Handle::from_bytes(Vec::new());
+        cache_in_memory("file:///tmp/cover.png", handle.clone());
+        assert!(cached_in_memory("file:///tmp/cover.png").is_none());
+        cache_in_memory("https://example.com/cover.png", handle);
+        assert!(cached_in_memory("https://example.com/cover.png").is_some());
+    }
+
+    #[test]
+    fn cache_names_follow_urls() {
+        assert_eq!(
+            cache_file_name("https://example.com/a.jpg"),
+            cache_file_name("https://example.com/a.jpg")
+        );
+        assert_ne!(
+            cache_file_name("https://example.com/a.jpg"),
+            cache_file_name("https://example.com/b.jpg")
+        );
+    }
+}
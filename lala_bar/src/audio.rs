@@ -0,0 +1,254 @@
+mod pipewire;
+
+use std::path::PathBuf;
+use std::sync::{LazyLock, OnceLock};
+
+use futures::SinkExt;
+use futures::channel::mpsc::Sender;
+
+use crate::aximer::AlsaMixer;
+use crate::settings::AlsaPolicy;
+
+/// The volume of the default output
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct Volume {
+    /// The volume of every channel in percent
+    pub channels: Vec<i64>,
+    /// The position of every channel, like FL or LFE
+    pub positions: Vec<String>,
+    pub mute: bool,
+}
+
+impl Volume {
+    pub fn left(&self) -> i64 {
+        self.channels.first().copied().unwrap_or(0)
+    }
+
+    /// The second channel, or the first one for mono outputs
+    pub fn right(&self) -> i64 {
+        self.channels
+            .get(1)
+            .or(self.channels.first())
+            .copied()
+            .unwrap_or(0)
+    }
+}
+
+/// An application playing or capturing sound
+#[derive(Debug, Clone, PartialEq)]
+pub struct AppStream {
+    pub id: u32,
+    /// The stream is opened by the bar itself, like the capture of the spectrum chart
+    pub own: bool,
+    pub name: String,
+    /// What is played, like the title of a browser tab
+    pub media: String,
+    pub icon: Option<PathBuf>,
+    pub volume: Volume,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum DeviceKind {
+    Output,
+    Input,
+}
+
+/// A sink or a source
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Device {
+    /// The node name, which is what the default device is set to
+    pub name: String,
+    pub description: String,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Profile {
+    pub index: u32,
+    pub description: String,
+    pub available: bool,
+}
+
+/// A sound card, with its profiles like A2DP and HSP/HFP for a headset
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Card {
+    pub id: u32,
+    pub description: String,
+    pub profiles: Vec<Profile>,
+    /// The index of the active profile
+    pub active: Option<u32>,
+}
+
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct Devices {
+    pub outputs: Vec<Device>,
+    pub inputs: Vec<Device>,
+    pub cards: Vec<Card>,
+    pub default_output: Option<String>,
+    pub default_input: Option<String>,
+}
+
+impl Devices {
+    pub fn is_default(&self, kind: DeviceKind, name: &str) -> bool {
+        let default = match kind {
+            DeviceKind::Output => &self.default_output,
+            DeviceKind::Input => &self.default_input,
+        };
+        default.as_deref() == Some(name)
+    }
+}
+
+/// The two last default outputs, to switch back and forth between them
+#[derive(Debug, Clone, Default)]
+pub struct RecentOutputs {
+    current: Option<String>,
+    previous: Option<String>,
+}
+
+impl RecentOutputs {
+    pub fn update(&mut self, default: Option<&str>) {
+        let Some(default) = default else {
+            return;
+        };
+        if self.current.as_deref() == Some(default) {
+            return;
+        }
+        self.previous = self.current.replace(default.to_owned());
+    }
+
+    /// The output used before the default one, if it is still there
+    pub fn previous<'a>(&self, devices: &'a Devices) -> Option<&'a Device> {
+        let previous = self.previous.as_deref()?;
+        devices
+            .outputs
+            .iter()
+            .find(|device| device.name == previous)
+    }
+}
+
+/// Where the volume of the default output is read and changed
+pub trait VolumeBackend: Send + Sync {
+    fn name(&self) -> &'static str;
+
+    /// None if there is no output, or it cannot be read
+    fn volume(&self) -> Option<Volume>;
+
+    /// Set the volume of every channel in percent, the missing channels are left as they are
+    fn set_channels(&self, channels: &[i64]);
+
+    fn set_mute(&self, mute: bool);
+
+    /// The applications playing sound, empty if the backend cannot tell them apart
+    fn streams(&self) -> Vec<AppStream> {
+        Vec::new()
+    }
+
+    fn set_stream_volume(&self, _id: u32, _percent: i64) {}
+
+    fn set_stream_mute(&self, _id: u32, _mute: bool) {}
+
+    /// The volume of the default input, None if there is none or the backend cannot read it
+    fn input_volume(&self) -> Option<Volume> {
+        None
+    }
+
+    /// Set all the channels of the default input to the same volume
+    fn set_input_level(&self, _percent: i64) {}
+
+    fn set_input_mute(&self, _mute: bool) {}
+
+    /// The applications capturing sound
+    fn captures(&self) -> Vec<AppStream> {
+        Vec::new()
+    }
+
+    /// The outputs, inputs and cards, empty if the backend cannot switch them
+    fn devices(&self) -> Devices {
+        Devices::default()
+    }
+
+    fn set_default_device(&self, _kind: DeviceKind, _name: &str) {}
+
+    fn set_profile(&self, _card: u32, _index: u32) {}
+}
+
+static BACKEND: OnceLock<Box<dyn VolumeBackend>> = OnceLock::new();
+
+// NOTE: PipeWire follows the default sink, ALSA is only used when there is no PipeWire daemon
+fn connect(alsa: AlsaPolicy) -> Box<dyn VolumeBackend> {
+    let backend: Box<dyn VolumeBackend> = match pipewire::PwMixer::connect() {
+        Ok(mixer) => Box::new(mixer),
+        Err(e) => {
+            tracing::warn!("cannot connect to pipewire, fall back to alsa: {e}");
+            Box::new(AlsaMixer::connect(alsa))
+        }
+    };
+    tracing::info!("volume backend: {}", backend.name());
+    backend
+}
+
+/// Connect to the mixer, with the ALSA control of the settings the bar has read
+pub fn init(alsa: AlsaPolicy) {
+    BACKEND.get_or_init(|| connect(alsa));
+}
+
+pub fn backend() -> &'static dyn VolumeBackend {
+    BACKEND
+        .get_or_init(|| connect(AlsaPolicy::default()))
+        .as_ref()
+}
+
+static VOLUME_CHANGED: LazyLock<tokio::sync::watch::Sender<()>> =
+    LazyLock::new(|| tokio::sync::watch::Sender::new(()));
+
+pub(crate) fn notify_changed() {
+    VOLUME_CHANGED.send_replace(());
+}
+
+/// Emits whenever the volume of the default output changes, another output becomes the default,
+/// or the streams or the devices change
+pub fn listen_changes() -> iced::Subscription<()> {
+    iced::Subscription::run(|| {
+        iced::stream::channel(10, |mut output: Sender<()>| async move {
+            let mut receiver = VOLUME_CHANGED.subscribe();
+            while receiver.changed().await.is_ok() {
+                if output.send(()).await.is_err() {
+                    break;
+                }
+            }
+        })
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn output(name: &str) -> Device {
+        Device {
+            name: name.to_owned(),
+            description: name.to_owned(),
+        }
+    }
+
+    #[test]
+    fn recent_outputs() {
+        let devices = Devices {
+            outputs: vec![output("speakers"), output("headset")],
+            ..Default::default()
+        };
+        let mut recent = RecentOutputs::default();
+        recent.update(Some("speakers"));
+        assert_eq!(recent.previous(&devices), None);
+        recent.update(None);
+        recent.update(Some("speakers"));
+        assert_eq!(recent.previous(&devices), None);
+        recent.update(Some("headset"));
+        assert_eq!(recent.previous(&devices), Some(&output("speakers")));
+        recent.update(Some("speakers"));
+        assert_eq!(recent.previous(&devices), Some(&output("headset")));
+        recent.update(Some("hdmi"));
+        assert_eq!(recent.previous(&devices), Some(&output("speakers")));
+        recent.update(Some("headset"));
+        assert_eq!(recent.previous(&devices), None);
+    }
+}
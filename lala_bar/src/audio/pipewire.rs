@@ -0,0 +1,999 @@
+use std::cell::RefCell;
+use std::collections::HashMap;
+use std::io::Cursor;
+use std::path::PathBuf;
+use std::rc::{Rc, Weak};
+use std::sync::{Arc, LazyLock, Mutex};
+use std::time::Duration;
+
+use pipewire as pw;
+use pw::spa::param::ParamType;
+use pw::spa::pod::deserialize::PodDeserializer;
+use pw::spa::pod::serialize::PodSerializer;
+use pw::spa::pod::{Object, Pod, Property, PropertyFlags, Value, ValueArray};
+use pw::spa::utils::SpaTypes;
+use pw::spa::utils::dict::DictRef;
+use pw::types::ObjectType;
+
+use super::{
+    AppStream, Card, Device, DeviceKind, Devices, Profile, Volume, VolumeBackend, notify_changed,
+};
+use crate::launcher::get_icon_path_from_xdgicon;
+
+/// The delay before connecting again once the daemon is gone, it is doubled after every failure
+const RECONNECT_DELAY: Duration = Duration::from_secs(1);
+const MAX_RECONNECT_DELAY: Duration = Duration::from_secs(60);
+
+/// The node a command is applied to
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Target {
+    DefaultSink,
+    DefaultSource,
+    Node(u32),
+}
+
+enum MixerCommand {
+    Channels(Target, Vec<i64>),
+    /// Set all the channels to the same volume
+    Level(Target, i64),
+    Mute(Target, bool),
+    Default(DeviceKind, String),
+    /// Activate a profile of a card, by their ids
+    Profile(u32, u32),
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum NodeKind {
+    Sink,
+    Source,
+    PlaybackStream,
+    CaptureStream,
+}
+
+impl NodeKind {
+    fn from_media_class(class: &str) -> Option<Self> {
+        match class {
+            "Audio/Sink" => Some(Self::Sink),
+            "Audio/Source" => Some(Self::Source),
+            "Stream/Output/Audio" => Some(Self::PlaybackStream),
+            "Stream/Input/Audio" => Some(Self::CaptureStream),
+            _ => None,
+        }
+    }
+}
+
+/// What is shown of a node, from its properties
+#[derive(Debug, Clone, Default, PartialEq)]
+struct NodeProps {
+    name: String,
+    description: String,
+    app_name: String,
+    media_name: String,
+    icon: Option<PathBuf>,
+    own: bool,
+    /// The card of a device node, and the index of the device in the profile of the card
+    card: Option<u32>,
+    card_device: Option<i32>,
+}
+
+/// The icons found by their name, the missing ones included
+static ICONS: LazyLock<Mutex<HashMap<String, Option<PathBuf>>>> =
+    LazyLock::new(|| Mutex::new(HashMap::new()));
+
+// NOTE: the info of a node is sent again on every change, and the icon themes are searched
+// on disk, so an icon is only looked up once
+fn icon_path(name: &str) -> Option<PathBuf> {
+    ICONS
+        .lock()
+        .unwrap()
+        .entry(name.to_owned())
+        .or_insert_with(|| get_icon_path_from_xdgicon(name))
+        .clone()
+}
+
+impl NodeProps {
+    fn from_dict(props: &DictRef) -> Self {
+        let get = |key: &str| props.get(key).unwrap_or_default().to_owned();
+        // NOTE: most players have no icon name, but their binary is named like their icon
+        let icon = props
+            .get(*pw::keys::APP_ICON_NAME)
+            .or(props.get(*pw::keys::APP_PROCESS_BINARY))
+            .and_then(icon_path);
+        let own = props
+            .get(*pw::keys::APP_PROCESS_ID)
+            .and_then(|pid| pid.parse::<u32>().ok())
+            == Some(std::process::id());
+        let description = [*pw::keys::NODE_DESCRIPTION, *pw::keys::NODE_NICK]
+            .into_iter()
+            .find_map(|key| props.get(key).filter(|value| !value.is_empty()))
+            .map(str::to_owned)
+            .unwrap_or_else(|| get(*pw::keys::NODE_NAME));
+        Self {
+            name: get(*pw::keys::NODE_NAME),
+            description,
+            app_name: get(*pw::keys::APP_NAME),
+            media_name: get(*pw::keys::MEDIA_NAME),
+            icon,
+            own,
+            card: props
+                .get(*pw::keys::DEVICE_ID)
+                .and_then(|id| id.parse().ok()),
+            card_device: props
+                .get("card.profile.device")
+                .and_then(|device| device.parse().ok()),
+        }
+    }
+}
+
+struct TrackedNode {
+    kind: NodeKind,
+    props: NodeProps,
+    node: pw::node::Node,
+    _listener: pw::node::NodeListener,
+    /// The linear volumes reported by PipeWire
+    channel_volumes: Vec<f32>,
+    /// The position of every channel, as SPA_AUDIO_CHANNEL ids
+    channel_map: Vec<u32>,
+    mute: bool,
+}
+
+impl TrackedNode {
+    fn volume(&self) -> Volume {
+        Volume {
+            channels: self
+                .channel_volumes
+                .iter()
+                .copied()
+                .map(to_percent)
+                .collect(),
+            positions: (0..self.channel_volumes.len())
+                .map(|index| channel_position(self.channel_map.get(index).copied(), index))
+                .collect(),
+            mute: self.mute,
+        }
+    }
+
+    fn stream(&self, id: u32) -> AppStream {
+        let props = &self.props;
+        AppStream {
+            id,
+            own: props.own,
+            name: [&props.app_name, &props.name]
+                .into_iter()
+                .find(|name| !name.is_empty())
+                .cloned()
+                .unwrap_or_default(),
+            media: props.media_name.clone(),
+            icon: props.icon.clone(),
+            volume: self.volume(),
+        }
+    }
+
+    fn device(&self) -> Device {
+        Device {
+            name: self.props.name.clone(),
+            description: self.props.description.clone(),
+        }
+    }
+}
+
+struct TrackedCard {
+    description: String,
+    device: pw::device::Device,
+    _listener: pw::device::DeviceListener,
+    profiles: Vec<Profile>,
+    active: Option<u32>,
+    /// The index of the last EnumProfile result, it goes back once they are enumerated again
+    enum_index: Option<u32>,
+    /// The index of the active route of every device, by the device index, the routes are sent
+    /// again once the profile changes
+    routes: HashMap<i32, i32>,
+}
+
+impl TrackedCard {
+    fn card(&self, id: u32) -> Card {
+        Card {
+            id,
+            description: self.description.clone(),
+            profiles: self.profiles.clone(),
+            active: self.active,
+        }
+    }
+}
+
+#[derive(Default)]
+struct Graph {
+    nodes: HashMap<u32, TrackedNode>,
+    cards: HashMap<u32, TrackedCard>,
+    /// The node names of the default sink and source, from the "default" metadata
+    default_sink: Option<String>,
+    default_source: Option<String>,
+    metadata: Option<(pw::metadata::Metadata, pw::metadata::MetadataListener)>,
+}
+
+impl Graph {
+    fn node(&self, target: Target) -> Option<&TrackedNode> {
+        match target {
+            Target::DefaultSink => {
+                let name = self.default_sink.as_ref()?;
+                self.nodes
+                    .values()
+                    .find(|node| node.kind == NodeKind::Sink && &node.props.name == name)
+            }
+            Target::DefaultSource => {
+                let name = self.default_source.as_ref()?;
+                self.nodes
+                    .values()
+                    .find(|node| node.kind == NodeKind::Source && &node.props.name == name)
+            }
+            Target::Node(id) => self.nodes.get(&id),
+        }
+    }
+
+    fn streams(&self, kind: NodeKind) -> Vec<AppStream> {
+        let mut streams: Vec<AppStream> = self
+            .nodes
+            .iter()
+            .filter(|(_, node)| node.kind == kind)
+            .map(|(id, node)| node.stream(*id))
+            .collect();
+        streams.sort_by_key(|stream| stream.id);
+        streams
+    }
+
+    fn state(&self) -> MixerState {
+        MixerState {
+            output: self.node(Target::DefaultSink).map(TrackedNode::volume),
+            input: self.node(Target::DefaultSource).map(TrackedNode::volume),
+            streams: self.streams(NodeKind::PlaybackStream),
+            captures: self.streams(NodeKind::CaptureStream),
+            devices: self.devices(),
+        }
+    }
+
+    fn devices(&self) -> Devices {
+        let devices_of = |kind| {
+            let mut devices: Vec<Device> = self
+                .nodes
+                .values()
+                .filter(|node| node.kind == kind)
+                .map(TrackedNode::device)
+                .collect();
+            devices.sort_by(|a, b| a.description.cmp(&b.description));
+            devices
+        };
+        let mut cards: Vec<Card> = self.cards.iter().map(|(id, card)| card.card(*id)).collect();
+        cards.sort_by(|a, b| a.description.cmp(&b.description));
+        Devices {
+            outputs: devices_of(NodeKind::Sink),
+            inputs: devices_of(NodeKind::Source),
+            cards,
+            default_output: self.default_sink.clone(),
+            default_input: self.default_source.clone(),
+        }
+    }
+}
+
+#[derive(Debug, Clone, Default, PartialEq)]
+struct MixerState {
+    output: Option<Volume>,
+    input: Option<Volume>,
+    streams: Vec<AppStream>,
+    captures: Vec<AppStream>,
+    devices: Devices,
+}
+
+// NOTE: the volumes are shown on a cubic scale, like pavucontrol and wpctl do
+fn to_percent(linear: f32) -> i64 {
+    (linear.max(0.).cbrt() * 100.).round() as i64
+}
+
+fn to_linear(percent: i64) -> f32 {
+    (percent.clamp(0, 150) as f32 / 100.).powi(3)
+}
+
+/// The default devices, the streams and the devices of PipeWire
+pub struct PwMixer {
+    state: Arc<Mutex<MixerState>>,
+    commands: pw::channel::Sender<MixerCommand>,
+}
+
+impl PwMixer {
+    pub fn connect() -> Result<Self, pw::Error> {
+        let (commands, receiver) = pw::channel::channel();
+        let (ready_sender, ready_receiver) = std::sync::mpsc::channel();
+        let state = Arc::new(Mutex::new(MixerState::default()));
+        let shared = state.clone();
+        std::thread::spawn(move || {
+            pw::init();
+            let (mut commands, result) = run(&shared, receiver, &ready_sender);
+            if let Err(e) = result {
+                let _ = ready_sender.send(Err(e));
+                return;
+            }
+            // NOTE: the daemon is restarted on updates or when it crashes, the mixer follows it
+            // once it is back
+            let mut delay = RECONNECT_DELAY;
+            loop {
+                std::thread::sleep(delay);
+                let (receiver, result) = run(&shared, commands, &ready_sender);
+                commands = receiver;
+                match result {
+                    Ok(()) => delay = RECONNECT_DELAY,
+                    Err(e) => {
+                        tracing::warn!("cannot connect to pipewire again: {e}");
+                        delay = (delay * 2).min(MAX_RECONNECT_DELAY);
+                    }
+                }
+            }
+        });
+        ready_receiver
+            .recv()
+            .map_err(|_| pw::Error::CreationFailed)??;
+        Ok(Self { state, commands })
+    }
+
+    fn send(&self, command: MixerCommand) {
+        if self.commands.send(command).is_err() {
+            tracing::warn!("the pipewire mixer is gone");
+        }
+    }
+}
+
+impl VolumeBackend for PwMixer {
+    fn name(&self) -> &'static str {
+        "pipewire"
+    }
+
+    fn volume(&self) -> Option<Volume> {
+        self.state.lock().unwrap().output.clone()
+    }
+
+    fn set_channels(&self, channels: &[i64]) {
+        self.send(MixerCommand::Channels(
+            Target::DefaultSink,
+            channels.to_vec(),
+        ));
+    }
+
+    fn set_mute(&self, mute: bool) {
+        self.send(MixerCommand::Mute(Target::DefaultSink, mute));
+    }
+
+    fn streams(&self) -> Vec<AppStream> {
+        self.state.lock().unwrap().streams.clone()
+    }
+
+    fn set_stream_volume(&self, id: u32, percent: i64) {
+        self.send(MixerCommand::Level(Target::Node(id), percent));
+    }
+
+    fn set_stream_mute(&self, id: u32, mute: bool) {
+        self.send(MixerCommand::Mute(Target::Node(id), mute));
+    }
+
+    fn input_volume(&self) -> Option<Volume> {
+        self.state.lock().unwrap().input.clone()
+    }
+
+    fn set_input_level(&self, percent: i64) {
+        self.send(MixerCommand::Level(Target::DefaultSource, percent));
+    }
+
+    fn set_input_mute(&self, mute: bool) {
+        self.send(MixerCommand::Mute(Target::DefaultSource, mute));
+    }
+
+    fn captures(&self) -> Vec<AppStream> {
+        self.state.lock().unwrap().captures.clone()
+    }
+
+    fn devices(&self) -> Devices {
+        self.state.lock().unwrap().devices.clone()
+    }
+
+    fn set_default_device(&self, kind: DeviceKind, name: &str) {
+        self.send(MixerCommand::Default(kind, name.to_owned()));
+    }
+
+    fn set_profile(&self, card: u32, index: u32) {
+        self.send(MixerCommand::Profile(card, index));
+    }
+}
+
+fn publish(graph: &Graph, shared: &Mutex<MixerState>) {
+    let state = graph.state();
+    let mut shared = shared.lock().unwrap();
+    if *shared != state {
+        *shared = state;
+        notify_changed();
+    }
+}
+
+// NOTE: the values of enum spa_audio_channel, the aux channels start at 0x1000
+fn channel_position(channel: Option<u32>, index: usize) -> String {
+    let name = match channel {
+        Some(2) => "MONO",
+        Some(3) => "FL",
+        Some(4) => "FR",
+        Some(5) => "FC",
+        Some(6) => "LFE",
+        Some(7) => "SL",
+        Some(8) => "SR",
+        Some(9) => "FLC",
+        Some(10) => "FRC",
+        Some(11) => "RC",
+        Some(12) => "RL",
+        Some(13) => "RR",
+        Some(aux @ 0x1000..0x2000) => return format!("AUX{}", aux - 0x1000),
+        _ => return format!("CH{}", index + 1),
+    };
+    name.to_owned()
+}
+
+/// The changed properties of a node
+#[derive(Default)]
+struct PropsUpdate {
+    volumes: Option<Vec<f32>>,
+    channel_map: Option<Vec<u32>>,
+    mute: Option<bool>,
+}
+
+fn parse_props(param: &Pod) -> PropsUpdate {
+    let mut update = PropsUpdate::default();
+    let Ok((_, Value::Object(object))) = PodDeserializer::deserialize_any_from(param.as_bytes())
+    else {
+        return update;
+    };
+    for property in object.properties {
+        match (property.key, property.value) {
+            (pw::spa::sys::SPA_PROP_channelVolumes, Value::ValueArray(ValueArray::Float(list))) => {
+                update.volumes = Some(list)
+            }
+            (pw::spa::sys::SPA_PROP_channelMap, Value::ValueArray(ValueArray::Id(list))) => {
+                update.channel_map = Some(list.into_iter().map(|id| id.0).collect())
+            }
+            (pw::spa::sys::SPA_PROP_mute, Value::Bool(value)) => update.mute = Some(value),
+            _ => {}
+        }
+    }
+    update
+}
+
+/// The index of a profile, and the profile if it is an entry of EnumProfile
+fn parse_profile(param: &Pod) -> Option<(u32, Option<Profile>)> {
+    let Ok((_, Value::Object(object))) = PodDeserializer::deserialize_any_from(param.as_bytes())
+    else {
+        return None;
+    };
+    let mut index = None;
+    let mut description = None;
+    let mut available = true;
+    for property in object.properties {
+        match (property.key, property.value) {
+            (pw::spa::sys::SPA_PARAM_PROFILE_index, Value::Int(value)) => {
+                index = u32::try_from(value).ok()
+            }
+            (pw::spa::sys::SPA_PARAM_PROFILE_description, Value::String(value)) => {
+                description = Some(value)
+            }
+            (pw::spa::sys::SPA_PARAM_PROFILE_available, Value::Id(value)) => {
+                available = value.0 != pw::spa::sys::SPA_PARAM_AVAILABILITY_no
+            }
+            _ => {}
+        }
+    }
+    let index = index?;
+    Some((
+        index,
+        description.map(|description| Profile {
+            index,
+            description,
+            available,
+        }),
+    ))
+}
+
+/// The index and the device of an active route
+fn parse_route(param: &Pod) -> Option<(i32, i32)> {
+    let Ok((_, Value::Object(object))) = PodDeserializer::deserialize_any_from(param.as_bytes())
+    else {
+        return None;
+    };
+    let mut index = None;
+    let mut device = None;
+    for property in object.properties {
+        match (property.key, property.value) {
+            (pw::spa::sys::SPA_PARAM_ROUTE_index, Value::Int(value)) => index = Some(value),
+            (pw::spa::sys::SPA_PARAM_ROUTE_device, Value::Int(value)) => device = Some(value),
+            _ => {}
+        }
+    }
+    Some((index?, device?))
+}
+
+fn object_pod(type_: SpaTypes, id: ParamType, properties: Vec<Property>) -> Option<Vec<u8>> {
+    let (cursor, _) = PodSerializer::serialize(
+        Cursor::new(Vec::new()),
+        &Value::Object(Object {
+            type_: type_.as_raw(),
+            id: id.as_raw(),
+            properties,
+        }),
+    )
+    .ok()?;
+    Some(cursor.into_inner())
+}
+
+fn set_profile(device: &pw::device::Device, index: u32) {
+    let properties = vec![
+        Property {
+            key: pw::spa::sys::SPA_PARAM_PROFILE_index,
+            flags: PropertyFlags::empty(),
+            value: Value::Int(index as i32),
+        },
+        Property {
+            key: pw::spa::sys::SPA_PARAM_PROFILE_save,
+            flags: PropertyFlags::empty(),
+            value: Value::Bool(true),
+        },
+    ];
+    let Some(bytes) = object_pod(SpaTypes::ObjectParamProfile, ParamType::Profile, properties)
+    else {
+        return;
+    };
+    if let Some(pod) = Pod::from_bytes(&bytes) {
+        device.set_param(ParamType::Profile, 0, pod);
+    }
+}
+
+fn set_props(node: &pw::node::Node, properties: Vec<Property>) {
+    let Some(bytes) = object_pod(SpaTypes::ObjectParamProps, ParamType::Props, properties) else {
+        return;
+    };
+    if let Some(pod) = Pod::from_bytes(&bytes) {
+        node.set_param(ParamType::Props, 0, pod);
+    }
+}
+
+fn set_route_props(
+    device: &pw::device::Device,
+    route: i32,
+    card_device: i32,
+    properties: Vec<Property>,
+) {
+    let props = Value::Object(Object {
+        type_: SpaTypes::ObjectParamProps.as_raw(),
+        id: ParamType::Route.as_raw(),
+        properties,
+    });
+    let properties = vec![
+        Property {
+            key: pw::spa::sys::SPA_PARAM_ROUTE_index,
+            flags: PropertyFlags::empty(),
+            value: Value::Int(route),
+        },
+        Property {
+            key: pw::spa::sys::SPA_PARAM_ROUTE_device,
+            flags: PropertyFlags::empty(),
+            value: Value::Int(card_device),
+        },
+        Property {
+            key: pw::spa::sys::SPA_PARAM_ROUTE_props,
+            flags: PropertyFlags::empty(),
+            value: props,
+        },
+        Property {
+            key: pw::spa::sys::SPA_PARAM_ROUTE_save,
+            flags: PropertyFlags::empty(),
+            value: Value::Bool(true),
+        },
+    ];
+    let Some(bytes) = object_pod(SpaTypes::ObjectParamRoute, ParamType::Route, properties) else {
+        return;
+    };
+    if let Some(pod) = Pod::from_bytes(&bytes) {
+        device.set_param(ParamType::Route, 0, pod);
+    }
+}
+
+// NOTE: the volume of a card device is kept by its route, like the speakers or the headphones,
+// and the session manager puts it back on the node whenever the route changes, so it is set on
+// the route when there is one
+fn set_node_props(graph: &Graph, node: &TrackedNode, properties: Vec<Property>) {
+    let route = node
+        .props
+        .card
+        .zip(node.props.card_device)
+        .and_then(|(card, card_device)| {
+            let card = graph.cards.get(&card)?;
+            Some((card, *card.routes.get(&card_device)?, card_device))
+        });
+    match route {
+        Some((card, route, card_device)) => {
+            set_route_props(&card.device, route, card_device, properties)
+        }
+        None => set_props(&node.node, properties),
+    }
+}
+
+fn channel_volumes(volumes: Vec<f32>) -> Property {
+    Property {
+        key: pw::spa::sys::SPA_PROP_channelVolumes,
+        flags: PropertyFlags::empty(),
+        value: Value::ValueArray(ValueArray::Float(volumes)),
+    }
+}
+
+fn apply(graph: &Graph, command: MixerCommand) {
+    let (target, property) = match command {
+        MixerCommand::Default(kind, name) => {
+            let Some((metadata, _)) = &graph.metadata else {
+                return;
+            };
+            // NOTE: the configured default is saved by the session manager, and becomes the
+            // default whenever the device is there
+            let key = match kind {
+                DeviceKind::Output => "default.configured.audio.sink",
+                DeviceKind::Input => "default.configured.audio.source",
+            };
+            let value = serde_json::json!({ "name": name }).to_string();
+            metadata.set_property(0, key, Some("Spa:String:JSON"), Some(&value));
+            return;
+        }
+        MixerCommand::Profile(card, index) => {
+            if let Some(card) = graph.cards.get(&card) {
+                set_profile(&card.device, index);
+            }
+            return;
+        }
+        MixerCommand::Channels(target, channels) => {
+            let Some(node) = graph.node(target) else {
+                return;
+            };
+            let mut volumes = node.channel_volumes.clone();
+            for (volume, percent) in volumes.iter_mut().zip(channels) {
+                *volume = to_linear(percent);
+            }
+            (target, channel_volumes(volumes))
+        }
+        MixerCommand::Level(target, percent) => {
+            let Some(node) = graph.node(target) else {
+                return;
+            };
+            let volumes = vec![to_linear(percent); node.channel_volumes.len()];
+            (target, channel_volumes(volumes))
+        }
+        MixerCommand::Mute(target, mute) => (
+            target,
+            Property {
+                key: pw::spa::sys::SPA_PROP_mute,
+                flags: PropertyFlags::empty(),
+                value: Value::Bool(mute),
+            },
+        ),
+    };
+    if let Some(node) = graph.node(target) {
+        set_node_props(graph, node, vec![property]);
+    }
+}
+
+// NOTE: the value is like {"name":"alsa_output.pci-0000_00_1f.3.analog-stereo"}
+fn default_name(value: &str) -> Option<String> {
+    let value: serde_json::Value = serde_json::from_str(value).ok()?;
+    Some(value.get("name")?.as_str()?.to_owned())
+}
+
+fn add_node(
+    registry: &pw::registry::Registry,
+    global: &pw::registry::GlobalObject<&DictRef>,
+    kind: NodeKind,
+    graph: &Weak<RefCell<Graph>>,
+    shared: &Arc<Mutex<MixerState>>,
+) -> Result<TrackedNode, pw::Error> {
+    let props = global.props.map(NodeProps::from_dict).unwrap_or_default();
+    let node: pw::node::Node = registry.bind(global)?;
+    let id = global.id;
+    let listener = node
+        .add_listener_local()
+        .info({
+            let graph = graph.clone();
+            let shared = shared.clone();
+            move |info| {
+                // NOTE: the media name follows what is played, like the title of a browser tab
+                let (Some(graph), Some(props)) = (graph.upgrade(), info.props()) else {
+                    return;
+                };
+                let mut graph = graph.borrow_mut();
+                if let Some(node) = graph.nodes.get_mut(&id) {
+                    node.props = NodeProps::from_dict(props);
+                }
+                publish(&graph, &shared);
+            }
+        })
+        .param({
+            let graph = graph.clone();
+            let shared = shared.clone();
+            move |_, param_type, _, _, param| {
+                if param_type != ParamType::Props {
+                    return;
+                }
+                let (Some(graph), Some(param)) = (graph.upgrade(), param) else {
+                    return;
+                };
+                let update = parse_props(param);
+                let mut graph = graph.borrow_mut();
+                if let Some(node) = graph.nodes.get_mut(&id) {
+                    if let Some(volumes) = update.volumes {
+                        node.channel_volumes = volumes;
+                    }
+                    if let Some(channel_map) = update.channel_map {
+                        node.channel_map = channel_map;
+                    }
+                    if let Some(mute) = update.mute {
+                        node.mute = mute;
+                    }
+                }
+                publish(&graph, &shared);
+            }
+        })
+        .register();
+    node.subscribe_params(&[ParamType::Props]);
+    Ok(TrackedNode {
+        kind,
+        props,
+        node,
+        _listener: listener,
+        channel_volumes: Vec::new(),
+        channel_map: Vec::new(),
+        mute: false,
+    })
+}
+
+fn add_card(
+    registry: &pw::registry::Registry,
+    global: &pw::registry::GlobalObject<&DictRef>,
+    graph: &Weak<RefCell<Graph>>,
+    shared: &Arc<Mutex<MixerState>>,
+) -> Result<TrackedCard, pw::Error> {
+    let description = global
+        .props
+        .and_then(|props| {
+            [*pw::keys::DEVICE_DESCRIPTION, *pw::keys::DEVICE_NAME]
+                .into_iter()
+                .find_map(|key| props.get(key))
+        })
+        .unwrap_or_default()
+        .to_owned();
+    let device: pw::device::Device = registry.bind(global)?;
+    let id = global.id;
+    let graph = graph.clone();
+    let shared = shared.clone();
+    let listener = device
+        .add_listener_local()
+        .param(move |_, param_type, result_index, _, param| {
+            let (Some(graph), Some(param)) = (graph.upgrade(), param) else {
+                return;
+            };
+            let mut graph = graph.borrow_mut();
+            let Some(card) = graph.cards.get_mut(&id) else {
+                return;
+            };
+            if param_type == ParamType::Route {
+                if let Some((route, card_device)) = parse_route(param) {
+                    card.routes.insert(card_device, route);
+                }
+                return;
+            }
+            let Some((index, profile)) = parse_profile(param) else {
+                return;
+            };
+            if param_type == ParamType::Profile {
+                card.active = Some(index);
+            } else if param_type == ParamType::EnumProfile
+                && let Some(profile) = profile
+            {
+                // NOTE: the profiles are enumerated again whenever they change, like on hotplug,
+                // so the ones which are gone are dropped when a new enumeration starts
+                if card.enum_index.is_some_and(|last| result_index <= last) {
+                    card.profiles.clear();
+                }
+                card.enum_index = Some(result_index);
+                card.profiles.retain(|known| known.index != index);
+                card.profiles.push(profile);
+                card.profiles.sort_by_key(|profile| profile.index);
+            }
+            publish(&graph, &shared);
+        })
+        .register();
+    device.subscribe_params(&[ParamType::EnumProfile, ParamType::Profile, ParamType::Route]);
+    Ok(TrackedCard {
+        description,
+        device,
+        _listener: listener,
+        profiles: Vec::new(),
+        active: None,
+        enum_index: None,
+        routes: HashMap::new(),
+    })
+}
+
+fn add_metadata(
+    registry: &pw::registry::Registry,
+    global: &pw::registry::GlobalObject<&DictRef>,
+    graph: &Weak<RefCell<Graph>>,
+    shared: &Arc<Mutex<MixerState>>,
+) -> Result<(pw::metadata::Metadata, pw::metadata::MetadataListener), pw::Error> {
+    let metadata: pw::metadata::Metadata = registry.bind(global)?;
+    let graph = graph.clone();
+    let shared = shared.clone();
+    let listener = metadata
+        .add_listener_local()
+        .property(move |subject, key, _, value| {
+            if subject != 0 {
+                return 0;
+            }
+            let Some(graph) = graph.upgrade() else {
+                return 0;
+            };
+            let mut graph = graph.borrow_mut();
+            // NOTE: all the properties are removed at once with a None key
+            let default = value.and_then(default_name);
+            match key {
+                Some("default.audio.sink") => graph.default_sink = default,
+                Some("default.audio.source") => graph.default_source = default,
+                None => {
+                    graph.default_sink = None;
+                    graph.default_source = None;
+                }
+                _ => return 0,
+            }
+            publish(&graph, &shared);
+            0
+        })
+        .register();
+    Ok((metadata, listener))
+}
+
+type Connection = (
+    pw::main_loop::MainLoopRc,
+    pw::context::ContextRc,
+    pw::core::CoreRc,
+    pw::registry::RegistryRc,
+);
+
+fn connect_daemon() -> Result<Connection, pw::Error> {
+    let mainloop = pw::main_loop::MainLoopRc::new(None)?;
+    let context = pw::context::ContextRc::new(&mainloop, None)?;
+    let core = context.connect_rc(None)?;
+    let registry = core.get_registry_rc()?;
+    Ok((mainloop, context, core, registry))
+}
+
+/// Follow the graph until the connection is lost, the commands are given back to connect again
+fn run(
+    shared: &Arc<Mutex<MixerState>>,
+    commands: pw::channel::Receiver<MixerCommand>,
+    ready: &std::sync::mpsc::Sender<Result<(), pw::Error>>,
+) -> (pw::channel::Receiver<MixerCommand>, Result<(), pw::Error>) {
+    let (mainloop, _context, core, registry) = match connect_daemon() {
+        Ok(connection) => connection,
+        Err(e) => return (commands, Err(e)),
+    };
+    let _ = ready.send(Ok(()));
+
+    let graph = Rc::new(RefCell::new(Graph::default()));
+
+    let _core_listener = core
+        .add_listener_local()
+        .error({
+            let mainloop = mainloop.clone();
+            move |id, _, res, message| {
+                tracing::warn!("pipewire error on {id}: {res} {message}");
+                // NOTE: the connection to the daemon is lost
+                if id == 0 {
+                    mainloop.quit();
+                }
+            }
+        })
+        .register();
+
+    let attached = commands.attach(mainloop.loop_(), {
+        let graph = Rc::downgrade(&graph);
+        move |command| {
+            if let Some(graph) = graph.upgrade() {
+                apply(&graph.borrow(), command);
+            }
+        }
+    });
+
+    let _registry_listener = registry
+        .add_listener_local()
+        .global({
+            let graph = Rc::downgrade(&graph);
+            let registry = registry.downgrade();
+            let shared = shared.clone();
+            move |global| {
+                let (Some(registry), Some(strong)) = (registry.upgrade(), graph.upgrade()) else {
+                    return;
+                };
+                let props = global.props;
+                let kind = props
+                    .and_then(|props| props.get(*pw::keys::MEDIA_CLASS))
+                    .and_then(NodeKind::from_media_class);
+                match (&global.type_, kind) {
+                    (ObjectType::Node, Some(kind)) => {
+                        match add_node(&registry, global, kind, &graph, &shared) {
+                            Ok(node) => {
+                                strong.borrow_mut().nodes.insert(global.id, node);
+                            }
+                            Err(e) => tracing::warn!("cannot bind the node {}: {e}", global.id),
+                        }
+                    }
+                    (ObjectType::Device, _)
+                        if props.and_then(|props| props.get(*pw::keys::MEDIA_CLASS))
+                            == Some("Audio/Device") =>
+                    {
+                        match add_card(&registry, global, &graph, &shared) {
+                            Ok(card) => {
+                                strong.borrow_mut().cards.insert(global.id, card);
+                            }
+                            Err(e) => tracing::warn!("cannot bind the card {}: {e}", global.id),
+                        }
+                    }
+                    (ObjectType::Metadata, _)
+                        if props.and_then(|props| props.get("metadata.name"))
+                            == Some("default") =>
+                    {
+                        match add_metadata(&registry, global, &graph, &shared) {
+                            Ok(metadata) => strong.borrow_mut().metadata = Some(metadata),
+                            Err(e) => tracing::warn!("cannot bind the default metadata: {e}"),
+                        }
+                    }
+                    _ => {}
+                }
+            }
+        })
+        .global_remove({
+            let graph = Rc::downgrade(&graph);
+            let shared = shared.clone();
+            move |id| {
+                if let Some(graph) = graph.upgrade() {
+                    let mut graph = graph.borrow_mut();
+                    let removed = graph.nodes.remove(&id).is_some();
+                    if removed || graph.cards.remove(&id).is_some() {
+                        publish(&graph, &shared);
+                    }
+                }
+            }
+        })
+        .register();
+
+    mainloop.run();
+    *shared.lock().unwrap() = MixerState::default();
+    notify_changed();
+    (attached.deattach(), Ok(()))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn cubic_volume() {
+        assert_eq!(to_percent(1.), 100);
+        assert_eq!(to_percent(0.125), 50);
+        assert_eq!(to_percent(to_linear(37)), 37);
+        assert_eq!(to_linear(0), 0.);
+        assert_eq!(to_linear(200), to_linear(150));
+    }
+
+    #[test]
+    fn default_sink_name() {
+        assert_eq!(
+            default_name(r#"{"name":"alsa_output.usb"}"#).as_deref(),
+            Some("alsa_output.usb")
+        );
+        assert_eq!(default_name("alsa_output.usb"), None);
+    }
+}
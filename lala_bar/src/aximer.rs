@@ -1,77 +1,234 @@
-use alsa::mixer::{Mixer, SelemChannelId, SelemId};
-
-fn get_volume(volume: i64) -> i64 {
-    (volume as f64 * 65536. / 100.) as i64
+use alsa::Round;
+use alsa::mixer::{MilliBel, Mixer, Selem, SelemChannelId, SelemId};
+
+use crate::audio::{Volume, VolumeBackend, notify_changed};
+use crate::settings::AlsaPolicy;
+
+// NOTE: the same mapping as alsamixer, so both show the same percents. Up to 24 dB the volume is
+// linear in dB, above it follows the loudness
+const MAX_LINEAR_DB_SCALE: i64 = 2400;
+
+fn percent_from_db(db: i64, min: i64, max: i64) -> i64 {
+    let normalized = if max - min <= MAX_LINEAR_DB_SCALE {
+        (db - min) as f64 / (max - min) as f64
+    } else {
+        let min_norm = 10f64.powf((min - max) as f64 / 6000.);
+        (10f64.powf((db - max) as f64 / 6000.) - min_norm) / (1. - min_norm)
+    };
+    (normalized * 100.).round() as i64
 }
 
-fn get_volume_from_alsa(value: i64) -> i64 {
-    (value as f64 * 100. / 65536.) as i64
+fn db_from_percent(percent: i64, min: i64, max: i64) -> i64 {
+    let normalized = percent.clamp(0, 100) as f64 / 100.;
+    if max - min <= MAX_LINEAR_DB_SCALE {
+        return min + ((max - min) as f64 * normalized).round() as i64;
+    }
+    let min_norm = 10f64.powf((min - max) as f64 / 6000.);
+    let value = normalized * (1. - min_norm) + min_norm;
+    if value <= 0. {
+        return min;
+    }
+    max + (6000. * value.log10()).round() as i64
 }
-pub fn get_left() -> Option<i64> {
-    let mixer = Mixer::new("default", false).ok()?;
-
-    // Find the master control
-    let sid = SelemId::new("Master", 0);
-    let selem = mixer
-        .find_selem(&sid)
-        .ok_or("Master control not found")
-        .ok()?;
 
-    // Define the left and right channel IDs
-    let left = SelemChannelId::FrontLeft;
+fn percent_from_raw(value: i64, min: i64, max: i64) -> i64 {
+    if max <= min {
+        return 0;
+    }
+    ((value - min) as f64 * 100. / (max - min) as f64).round() as i64
+}
 
-    Some(get_volume_from_alsa(selem.get_playback_volume(left).ok()?))
+fn raw_from_percent(percent: i64, min: i64, max: i64) -> i64 {
+    min + ((max - min) as f64 * percent.clamp(0, 100) as f64 / 100.).round() as i64
 }
 
-pub fn get_right() -> Option<i64> {
-    let mixer = Mixer::new("default", false).ok()?;
+fn position(channel: SelemChannelId) -> &'static str {
+    match channel {
+        SelemChannelId::FrontLeft => "FL",
+        SelemChannelId::FrontRight => "FR",
+        SelemChannelId::RearLeft => "RL",
+        SelemChannelId::RearRight => "RR",
+        SelemChannelId::FrontCenter => "FC",
+        SelemChannelId::Woofer => "LFE",
+        SelemChannelId::SideLeft => "SL",
+        SelemChannelId::SideRight => "SR",
+        SelemChannelId::RearCenter => "RC",
+        _ => "UNKNOWN",
+    }
+}
 
-    // Find the master control
-    let sid = SelemId::new("Master", 0);
-    let selem = mixer
-        .find_selem(&sid)
-        .ok_or("Master control not found")
-        .ok()?;
+/// The playback channels of the control, a mono control has only one
+fn channels(selem: &Selem) -> Vec<SelemChannelId> {
+    if selem.is_playback_mono() {
+        return vec![SelemChannelId::mono()];
+    }
+    SelemChannelId::all()
+        .iter()
+        .copied()
+        .filter(|channel| {
+            !matches!(channel, SelemChannelId::Unknown | SelemChannelId::Last)
+                && selem.has_playback_channel(*channel)
+        })
+        .collect()
+}
 
-    // Define the left and right channel IDs
-    let right = SelemChannelId::FrontRight;
+/// How the values of a control are turned into percents
+enum Scale {
+    Db { min: i64, max: i64 },
+    Raw { min: i64, max: i64 },
+}
 
-    Some(get_volume_from_alsa(selem.get_playback_volume(right).ok()?))
+impl Scale {
+    fn of(selem: &Selem) -> Self {
+        let (MilliBel(min), MilliBel(max)) = selem.get_playback_db_range();
+        if min < max {
+            return Self::Db { min, max };
+        }
+        let (min, max) = selem.get_playback_volume_range();
+        Self::Raw { min, max }
+    }
+
+    fn get(&self, selem: &Selem, channel: SelemChannelId) -> Option<i64> {
+        match *self {
+            Self::Db { min, max } => {
+                let MilliBel(db) = selem.get_playback_vol_db(channel).ok()?;
+                Some(percent_from_db(db, min, max))
+            }
+            Self::Raw { min, max } => {
+                let value = selem.get_playback_volume(channel).ok()?;
+                Some(percent_from_raw(value, min, max))
+            }
+        }
+    }
+
+    fn set(&self, selem: &Selem, channel: SelemChannelId, percent: i64) -> Option<()> {
+        match *self {
+            Self::Db { min, max } => {
+                let db = MilliBel(db_from_percent(percent, min, max));
+                selem.set_playback_db(channel, db, Round::Floor).ok()
+            }
+            Self::Raw { min, max } => selem
+                .set_playback_volume(channel, raw_from_percent(percent, min, max))
+                .ok(),
+        }
+    }
 }
-pub fn set_left(value: i64) -> Option<i64> {
-    let mixer = Mixer::new("default", false).ok()?;
-
-    // Find the master control
-    let sid = SelemId::new("Master", 0);
-    let selem = mixer
-        .find_selem(&sid)
-        .ok_or("Master control not found")
-        .ok()?;
-
-    // Define the left and right channel IDs
-    let left = SelemChannelId::FrontLeft;
-
-    // Set the volume for left and right channels (0 to 100)
-    let left_volume = get_volume(value); // Adjust this value as needed
-    selem.set_playback_volume(left, left_volume).ok()?;
-    Some(get_volume_from_alsa(selem.get_playback_volume(left).ok()?))
+
+/// A playback control of an ALSA card, "Master" of "default" unless configured
+pub struct AlsaMixer {
+    card: String,
+    control: String,
 }
 
-pub fn set_right(value: i64) -> Option<i64> {
-    let mixer = Mixer::new("default", false).ok()?;
+impl AlsaMixer {
+    pub fn connect(policy: AlsaPolicy) -> Self {
+        let card = policy.card.clone();
+        std::thread::spawn(move || {
+            if let Err(e) = watch_changes(&card) {
+                tracing::warn!("cannot watch the alsa mixer: {e}");
+            }
+        });
+        Self {
+            card: policy.card,
+            control: policy.control,
+        }
+    }
+
+    fn with_control<T>(&self, f: impl FnOnce(&Selem) -> Option<T>) -> Option<T> {
+        let mixer = Mixer::new(&self.card, false).ok()?;
+        let sid = SelemId::new(&self.control, 0);
+        let Some(selem) = mixer.find_selem(&sid) else {
+            tracing::warn!("control {} not found on {}", self.control, self.card);
+            return None;
+        };
+        f(&selem)
+    }
+}
 
-    // Find the master control
-    let sid = SelemId::new("Master", 0);
-    let selem = mixer
-        .find_selem(&sid)
-        .ok_or("Master control not found")
-        .ok()?;
+// NOTE: blocks on the poll descriptors of the mixer, so the changes made elsewhere are seen at once
+fn watch_changes(card: &str) -> alsa::Result<()> {
+    let mixer = Mixer::new(card, false)?;
+    loop {
+        mixer.wait(None)?;
+        mixer.handle_events()?;
+        notify_changed();
+    }
+}
 
-    // Define the left and right channel IDs
-    let right = SelemChannelId::FrontRight;
+impl VolumeBackend for AlsaMixer {
+    fn name(&self) -> &'static str {
+        "alsa"
+    }
+
+    fn volume(&self) -> Option<Volume> {
+        self.with_control(|selem| {
+            let scale = Scale::of(selem);
+            let channels = channels(selem);
+            let first = *channels.first()?;
+            let positions = if channels.len() == 1 {
+                vec!["MONO".to_owned()]
+            } else {
+                channels
+                    .iter()
+                    .map(|channel| position(*channel).to_owned())
+                    .collect()
+            };
+            // NOTE: the switch is on while the sound is played
+            let mute = selem.has_playback_switch() && selem.get_playback_switch(first).ok()? == 0;
+            Some(Volume {
+                channels: channels
+                    .iter()
+                    .map(|channel| scale.get(selem, *channel))
+                    .collect::<Option<_>>()?,
+                positions,
+                mute,
+            })
+        })
+    }
+
+    fn set_channels(&self, percents: &[i64]) {
+        self.with_control(|selem| {
+            let scale = Scale::of(selem);
+            for (channel, percent) in channels(selem).into_iter().zip(percents) {
+                scale.set(selem, channel, *percent)?;
+            }
+            Some(())
+        });
+    }
+
+    fn set_mute(&self, mute: bool) {
+        self.with_control(|selem| {
+            if !selem.has_playback_switch() {
+                return None;
+            }
+            selem.set_playback_switch_all(if mute { 0 } else { 1 }).ok()
+        });
+    }
+}
 
-    // Set the volume for left and right channels (0 to 100)
-    let right_volume = get_volume(value); // Adjust this value as needed
-    selem.set_playback_volume(right, right_volume).ok()?;
-    Some(get_volume_from_alsa(selem.get_playback_volume(right).ok()?))
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn db_scale() {
+        // a linear control, from -20 dB to 0 dB
+        assert_eq!(percent_from_db(-1000, -2000, 0), 50);
+        assert_eq!(db_from_percent(50, -2000, 0), -1000);
+        // a control from -65.25 dB, like the Master of most Intel cards
+        assert_eq!(percent_from_db(0, -6525, 0), 100);
+        assert_eq!(percent_from_db(-6525, -6525, 0), 0);
+        assert_eq!(percent_from_db(-1601, -6525, 0), 50);
+        assert_eq!(db_from_percent(50, -6525, 0), -1601);
+        assert_eq!(percent_from_db(db_from_percent(37, -6525, 0), -6525, 0), 37);
+        assert_eq!(db_from_percent(0, -6525, 0), -6525);
+    }
+
+    #[test]
+    fn raw_scale() {
+        assert_eq!(percent_from_raw(87, 0, 87), 100);
+        assert_eq!(percent_from_raw(0, 0, 0), 0);
+        assert_eq!(raw_from_percent(50, 0, 65536), 32768);
+        assert_eq!(raw_from_percent(150, 0, 87), 87);
+    }
 }
@@ -36,3 +36,22 @@ pub const PAUSE: &[u8] = include_bytes!("../assets/images/pause.svg");
 pub static PAUSE_HANDLE: LazyLock<svg::Handle> = LazyLock::new(|| svg::Handle::from_memory(PAUSE));
 
 pub const MAX_SHOWN_NOTIFICATIONS_COUNT: usize = 4;
+
+pub const NOTIFICATION_POPUP_HEIGHT: u32 = 130;
+
+// NOTE: the popup grows with the reply draft, until the draft reaches MAX_REPLY_LINES lines
+pub const REPLY_LINE_HEIGHT: u32 = 20;
+
+pub const MAX_REPLY_LINES: usize = 4;
+
+pub const MAX_REPLY_THREADS: usize = 20;
+
+pub const RATE_STEP: f64 = 0.25;
+
+// NOTE: the daemon counts the ids up from 0, so the last one is kept for the track notification,
+// which is replaced every time the track changes. Other senders cannot replace it
+pub const MEDIA_NOTIFICATION_ID: u32 = u32::MAX;
+
+pub const MEDIA_NOTIFICATION_TIMEOUT: i32 = 5;
+
+pub const MEDIA_NOTIFICATION_ART_SIZE: u32 = 128;
@@ -1,9 +1,43 @@
 use futures::channel::mpsc::Sender;
 
 use crate::Message;
+use crate::osd::Osd;
+use crate::zbus_mpirs::ServiceInfo;
+use serde::{Deserialize, Serialize};
+use zbus::object_server::SignalEmitter;
+use zbus::zvariant::{OwnedValue, Type, Value};
 use zbus::{connection, interface};
+
+pub const BACKEND_PATH: &str = "/org/lalabar/Backend";
+
+/// The track shown on the bar, all the fields are empty if there is no player
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize, Type, Value, OwnedValue)]
+pub struct NowPlaying {
+    pub player: String,
+    pub title: String,
+    pub artist: String,
+    pub album: String,
+    pub status: String,
+}
+
+impl NowPlaying {
+    pub fn new(data: Option<&ServiceInfo>) -> Self {
+        let Some(data) = data else {
+            return Self::default();
+        };
+        Self {
+            player: data.identity.clone(),
+            title: data.metadata.xesam_title.clone(),
+            artist: data.metadata.artists().join(", "),
+            album: data.metadata.album().to_owned(),
+            status: data.playback_status.clone(),
+        }
+    }
+}
+
 pub struct LalaBarBackend {
     sender: Sender<Message>,
+    now_playing: NowPlaying,
 }
 
 #[interface(name = "org.lalabar.Backend")]
@@ -11,12 +45,104 @@ impl LalaBarBackend {
     fn toggle_bar(&mut self) {
         self.sender.try_send(Message::ToggleLauncherDBus).ok();
     }
+
+    /// Dismiss the newest notification, like closing it by hand
+    fn dismiss_newest_notification(&mut self) {
+        self.sender.try_send(Message::DismissNewestNotify).ok();
+    }
+
+    /// Dismiss every notification, the senders are told they were closed by hand
+    fn dismiss_all_notifications(&mut self) {
+        self.sender.try_send(Message::DismissAllNotify).ok();
+    }
+
+    /// Invoke the default action of the newest notification
+    fn invoke_newest_notification(&mut self) {
+        self.sender.try_send(Message::InvokeNewestNotify).ok();
+    }
+
+    /// Focus the inline reply input of the newest notification
+    fn reply_newest_notification(&mut self) {
+        self.sender.try_send(Message::ReplyNewestNotify).ok();
+    }
+
+    /// Move the keyboard focus to the next visible popup,
+    /// after the last one the focus is given back to the compositor
+    fn focus_next_notification(&mut self) {
+        self.sender.try_send(Message::FocusNextNotify).ok();
+    }
+
+    /// Play or pause the player shown on the bar
+    fn play_pause(&mut self) {
+        self.sender.try_send(Message::RequestPlayPause).ok();
+    }
+
+    fn next(&mut self) {
+        self.sender.try_send(Message::RequestNext).ok();
+    }
+
+    fn previous(&mut self) {
+        self.sender.try_send(Message::RequestPre).ok();
+    }
+
+    /// Seek forward, or backward if the offset in microseconds is negative
+    fn seek(&mut self, offset: i64) {
+        self.sender.try_send(Message::SeekBy(offset)).ok();
+    }
+
+    /// Show the next running player on the bar
+    fn cycle_player(&mut self) {
+        self.sender.try_send(Message::CyclePlayer).ok();
+    }
+
+    /// Show the on-screen display, with an icon name or a glyph, a value from 0 to 1 and a label.
+    /// A negative value shows only the label
+    fn show_osd(&mut self, icon: String, value: f64, label: String) {
+        self.sender
+            .try_send(Message::ShowOsd(Osd::new(&icon, value, label)))
+            .ok();
+    }
+
+    #[zbus(property)]
+    fn now_playing(&self) -> NowPlaying {
+        self.now_playing.clone()
+    }
+
+    #[zbus(signal, name = "NowPlayingChanged")]
+    async fn emit_now_playing_changed(
+        emitter: &SignalEmitter<'_>,
+        now_playing: NowPlaying,
+    ) -> zbus::Result<()>;
+}
+
+/// Update the NowPlaying property, and emit the changes if it is different
+pub async fn update_now_playing(
+    conn: &zbus::Connection,
+    now_playing: NowPlaying,
+) -> zbus::Result<()> {
+    let backend = conn
+        .object_server()
+        .interface::<_, LalaBarBackend>(BACKEND_PATH)
+        .await?;
+    let mut iface = backend.get_mut().await;
+    if iface.now_playing == now_playing {
+        return Ok(());
+    }
+    iface.now_playing = now_playing.clone();
+    iface.now_playing_changed(backend.signal_emitter()).await?;
+    LalaBarBackend::emit_now_playing_changed(backend.signal_emitter(), now_playing).await
 }
 
 pub async fn start_backend(sender: Sender<Message>) -> Result<zbus::Connection, zbus::Error> {
     connection::Builder::session()?
         .name("org.lalabar.backend")?
-        .serve_at("/org/lalabar/Backend", LalaBarBackend { sender })?
+        .serve_at(
+            BACKEND_PATH,
+            LalaBarBackend {
+                sender,
+                now_playing: NowPlaying::default(),
+            },
+        )?
         .build()
         .await
 }
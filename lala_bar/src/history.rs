@@ -0,0 +1,309 @@
+use std::collections::HashMap;
+use std::path::PathBuf;
+use std::time::{Duration, Instant};
+
+use chrono::{DateTime, Local, TimeDelta};
+use serde::{Deserialize, Serialize};
+use tokio::io::AsyncWriteExt;
+
+use crate::zbus_mpirs::ServiceInfo;
+
+/// A track is recorded once more than half of it, or this long, is listened to
+const SCROBBLE_DURATION: Duration = Duration::from_secs(4 * 60);
+
+const TOP_COUNT: usize = 5;
+
+/// A track which is back in its first seconds is listened to again, like a track on repeat
+const RESTART_WINDOW: Duration = Duration::from_secs(5);
+
+/// One recorded listen, a line of the history file
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct Play {
+    pub title: String,
+    pub artists: Vec<String>,
+    pub album: String,
+    pub player: String,
+    pub started: DateTime<Local>,
+    /// The seconds the track was playing
+    pub listened: u64,
+}
+
+fn is_restart(expected: Duration, position: Duration) -> bool {
+    position < RESTART_WINDOW && expected > position + RESTART_WINDOW
+}
+
+fn is_scrobble(listened: Duration, length: Option<Duration>) -> bool {
+    listened >= SCROBBLE_DURATION
+        || length.is_some_and(|length| !length.is_zero() && listened > length / 2)
+}
+
+struct Listening {
+    track: String,
+    play: Play,
+    length: Option<Duration>,
+    listened: Duration,
+    playing_since: Option<Instant>,
+    // NOTE: the position at the last update, and how long it was played until then
+    position: Duration,
+    played_at_position: Duration,
+}
+
+impl Listening {
+    fn new(track: String, info: &ServiceInfo, position: Duration) -> Self {
+        let metadata = &info.metadata;
+        Self {
+            track,
+            play: Play {
+                title: metadata.xesam_title.clone(),
+                artists: metadata.artists().to_vec(),
+                album: metadata.album().to_owned(),
+                player: info.identity.clone(),
+                started: Local::now(),
+                listened: 0,
+            },
+            length: metadata
+                .mpris_length
+                .filter(|length| *length > 0)
+                .map(|length| Duration::from_micros(length as u64)),
+            listened: Duration::ZERO,
+            playing_since: None,
+            position,
+            played_at_position: Duration::ZERO,
+        }
+    }
+
+    fn played(&self) -> Duration {
+        self.listened
+            + self
+                .playing_since
+                .map_or(Duration::ZERO, |since| since.elapsed())
+    }
+
+    /// If the track went back to its start, instead of going on from the last update
+    fn restarted(&self, position: Duration) -> bool {
+        let expected = self.position + self.played().saturating_sub(self.played_at_position);
+        is_restart(expected, position)
+    }
+
+    fn seen_at(&mut self, position: Duration) {
+        self.position = position;
+        self.played_at_position = self.played();
+    }
+
+    fn set_playing(&mut self, playing: bool) {
+        match (playing, self.playing_since) {
+            (true, None) => self.playing_since = Some(Instant::now()),
+            (false, Some(since)) => {
+                self.listened += since.elapsed();
+                self.playing_since = None;
+            }
+            _ => {}
+        }
+    }
+
+    /// The play to record, None if not enough of it is listened to
+    fn finish(mut self) -> Option<Play> {
+        self.set_playing(false);
+        if !is_scrobble(self.listened, self.length) {
+            return None;
+        }
+        self.play.listened = self.listened.as_secs();
+        Some(self.play)
+    }
+}
+
+/// Follows what every player plays, and gives back the listens to record
+#[derive(Default)]
+pub struct ListenTracker {
+    listening: HashMap<String, Listening>,
+}
+
+impl ListenTracker {
+    /// Update with the state of the players, the listens which are finished are returned
+    pub fn update(&mut self, players: &[ServiceInfo]) -> Vec<Play> {
+        let mut finished = Vec::new();
+        let gone: Vec<String> = self
+            .listening
+            .keys()
+            .filter(|path| !players.iter().any(|info| info.service_path() == *path))
+            .cloned()
+            .collect();
+        for path in gone {
+            finished.extend(self.listening.remove(&path).and_then(Listening::finish));
+        }
+        for info in players {
+            let metadata = &info.metadata;
+            let track = format!("{}\n{}", metadata.track_id(), metadata.xesam_title);
+            let path = info.service_path().to_owned();
+            let position = Duration::from_micros(info.position().max(0) as u64);
+            if self
+                .listening
+                .get(&path)
+                .is_some_and(|listening| listening.track != track || listening.restarted(position))
+            {
+                finished.extend(self.listening.remove(&path).and_then(Listening::finish));
+            }
+            let listening = self
+                .listening
+                .entry(path)
+                .or_insert_with(|| Listening::new(track, info, position));
+            listening.set_playing(info.playback_status == "Playing");
+            listening.seen_at(position);
+        }
+        finished
+    }
+
+    /// Finish all the listens, like when the bar is closed
+    pub fn flush(&mut self) -> Vec<Play> {
+        self.listening
+            .drain()
+            .filter_map(|(_, listening)| listening.finish())
+            .collect()
+    }
+}
+
+fn data_file(name: &str) -> Option<PathBuf> {
+    xdg::BaseDirectories::with_prefix("lala-bar")
+        .place_data_file(name)
+        .ok()
+}
+
+pub async fn record(plays: Vec<Play>) {
+    let Some(path) = data_file("history.jsonl") else {
+        return;
+    };
+    let mut lines = String::new();
+    for play in plays {
+        if let Ok(line) = serde_json::to_string(&play) {
+            lines.push_str(&line);
+            lines.push('\n');
+        }
+    }
+    let file = tokio::fs::OpenOptions::new()
+        .create(true)
+        .append(true)
+        .open(&path)
+        .await;
+    if let Err(e) = match file {
+        Ok(mut file) => file.write_all(lines.as_bytes()).await,
+        Err(e) => Err(e),
+    } {
+        tracing::warn!("Cannot record the history: {e}");
+    }
+}
+
+// NOTE: the broken lines are skipped, so one bad write does not lose the whole history
+async fn read_plays() -> Vec<Play> {
+    let Some(path) = data_file("history.jsonl") else {
+        return Vec::new();
+    };
+    let Ok(content) = tokio::fs::read_to_string(path).await else {
+        return Vec::new();
+    };
+    content
+        .lines()
+        .filter_map(|line| serde_json::from_str(line).ok())
+        .collect()
+}
+
+/// The most played artists and tracks of the last week
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct HistoryStats {
+    pub top_artists: Vec<(String, usize)>,
+    /// The title and the artists of the tracks
+    pub top_tracks: Vec<((String, String), usize)>,
+}
+
+fn top<T: Ord + Clone>(counts: HashMap<T, usize>) -> Vec<(T, usize)> {
+    let mut counts: Vec<(T, usize)> = counts.into_iter().collect();
+    counts.sort_by(|(a, a_count), (b, b_count)| b_count.cmp(a_count).then_with(|| a.cmp(b)));
+    counts.truncate(TOP_COUNT);
+    counts
+}
+
+fn stats_since(plays: &[Play], since: DateTime<Local>) -> HistoryStats {
+    let mut artists = HashMap::new();
+    let mut tracks = HashMap::new();
+    for play in plays.iter().filter(|play| play.started >= since) {
+        for artist in &play.artists {
+            *artists.entry(artist.clone()).or_default() += 1;
+        }
+        *tracks
+            .entry((play.title.clone(), play.artists.join(", ")))
+            .or_default() += 1;
+    }
+    HistoryStats {
+        top_artists: top(artists),
+        top_tracks: top(tracks),
+    }
+}
+
+pub async fn weekly_stats() -> HistoryStats {
+    let plays = read_plays().await;
+    stats_since(&plays, Local::now() - TimeDelta::weeks(1))
+}
+
+/// Write the whole history as a json array, and return the path of the file
+pub async fn export() -> Option<PathBuf> {
+    let plays = read_plays().await;
+    let path = data_file("history.json")?;
+    let json = serde_json::to_string_pretty(&plays).ok()?;
+    match tokio::fs::write(&path, json).await {
+        Ok(_) => Some(path),
+        Err(e) => {
+            tracing::warn!("Cannot export the history: {e}");
+            None
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn scrobble_rule() {
+        let length = Some(Duration::from_secs(180));
+        assert!(!is_scrobble(Duration::from_secs(90), length));
+        assert!(is_scrobble(Duration::from_secs(91), length));
+        assert!(is_scrobble(Duration::from_secs(240), None));
+        assert!(!is_scrobble(Duration::from_secs(239), None));
+        assert!(!is_scrobble(Duration::from_secs(10), Some(Duration::ZERO)));
+    }
+
+    #[test]
+    fn restart_rule() {
+        let secs = Duration::from_secs;
+        assert!(is_restart(secs(200), secs(1)));
+        assert!(!is_restart(secs(4), secs(0)));
+        assert!(!is_restart(secs(200), secs(190)));
+        assert!(!is_restart(secs(200), secs(30)));
+    }
+
+    #[test]
+    fn weekly_tops() {
+        let now = Local::now();
+        let play = |title: &str, artist: &str, days: i64| Play {
+            title: title.to_owned(),
+            artists: vec![artist.to_owned()],
+            album: String::new(),
+            player: "mpv".to_owned(),
+            started: now - TimeDelta::days(days),
+            listened: 200,
+        };
+        let plays = [
+            play("a", "x", 1),
+            play("a", "x", 2),
+            play("b", "y", 3),
+            play("c", "y", 4),
+            play("d", "z", 10),
+        ];
+        let stats = stats_since(&plays, now - TimeDelta::weeks(1));
+        assert_eq!(
+            stats.top_artists,
+            vec![("x".to_owned(), 2), ("y".to_owned(), 2)]
+        );
+        assert_eq!(stats.top_tracks[0], (("a".to_owned(), "x".to_owned()), 2));
+        assert_eq!(stats.top_tracks.len(), 3);
+    }
+}
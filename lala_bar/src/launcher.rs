@@ -1,6 +1,7 @@
 mod applications;
 
 use crate::localization::fl;
+pub(crate) use applications::get_icon_path_from_xdgicon;
 use applications::{App, all_apps};
 use iced::widget::{column, scrollable, text_input};
 use iced::{Element, Event, Length, Task as Command};
@@ -91,7 +91,7 @@ static ICONS_SIZE: &[&str] = &["256x256", "128x128", "64x64", "48x48", "32x32",
 
 static THEMES_LIST: &[&str] = &["breeze", "Adwaita"];
 
-fn get_icon_path_from_xdgicon(iconname: &str) -> Option<PathBuf> {
+pub(crate) fn get_icon_path_from_xdgicon(iconname: &str) -> Option<PathBuf> {
     let top_icon_path = xdg::BaseDirectories::with_prefix("icons");
 
     // NOTE: shit application icon place
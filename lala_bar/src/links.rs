@@ -0,0 +1,96 @@
+use ashpd::desktop::open_uri::OpenFileRequest;
+
+/// The schemes which are always opened without asking
+pub const WEB_SCHEMES: [&str; 2] = ["http", "https"];
+
+/// The lowercase scheme of the uri, None if the uri has no valid scheme
+pub fn link_scheme(uri: &str) -> Option<String> {
+    let (scheme, _) = uri.split_once(':')?;
+    let mut chars = scheme.chars();
+    if !chars.next()?.is_ascii_alphabetic()
+        || !chars.all(|c| c.is_ascii_alphanumeric() || matches!(c, '+' | '-' | '.'))
+    {
+        return None;
+    }
+    Some(scheme.to_ascii_lowercase())
+}
+
+// NOTE: `{uri}` in the handler is replaced by the link, or the link is appended at the end
+fn handler_command(handler: &str, uri: &str) -> Option<(String, Vec<String>)> {
+    let mut parts = handler.split_whitespace();
+    let program = parts.next()?.to_owned();
+    let mut args: Vec<String> = parts.map(|part| part.replace("{uri}", uri)).collect();
+    if !handler.contains("{uri}") {
+        args.push(uri.to_owned());
+    }
+    Some((program, args))
+}
+
+async fn open_with_portal(uri: &str) -> anyhow::Result<()> {
+    let uri = ashpd::Uri::parse(uri)?;
+    OpenFileRequest::default().send_uri(&uri).await?;
+    Ok(())
+}
+
+pub async fn open_link(uri: String, handler: Option<String>, use_portal: bool) {
+    if let Some(handler) = handler {
+        let Some((program, args)) = handler_command(&handler, &uri) else {
+            tracing::warn!("Empty handler for link: {uri}");
+            return;
+        };
+        if let Err(e) = std::process::Command::new(&program).args(args).spawn() {
+            tracing::error!("Cannot open {uri} with {program}: {e}");
+        }
+        return;
+    }
+    // NOTE: the portal cannot open files by uri, they need to be passed by fd
+    if use_portal && link_scheme(&uri).is_some_and(|scheme| scheme != "file") {
+        match open_with_portal(&uri).await {
+            Ok(_) => return,
+            Err(e) => {
+                tracing::warn!("Cannot open {uri} with the portal: {e}, fallback to xdg-open")
+            }
+        }
+    }
+    open::that_in_background(&uri);
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn scheme_of_links() {
+        assert_eq!(link_scheme("https://example.com"), Some("https".to_owned()));
+        assert_eq!(
+            link_scheme("MailTo:me@example.com"),
+            Some("mailto".to_owned())
+        );
+        assert_eq!(
+            link_scheme("git+ssh://host/repo"),
+            Some("git+ssh".to_owned())
+        );
+        assert_eq!(link_scheme("example.com"), None);
+        assert_eq!(link_scheme("1abc:foo"), None);
+        assert_eq!(link_scheme(":foo"), None);
+    }
+
+    #[test]
+    fn handler_commands() {
+        assert_eq!(
+            handler_command("mpv --no-terminal", "rtsp://cam"),
+            Some((
+                "mpv".to_owned(),
+                vec!["--no-terminal".to_owned(), "rtsp://cam".to_owned()]
+            ))
+        );
+        assert_eq!(
+            handler_command("foot -e ssh {uri}", "ssh://host"),
+            Some((
+                "foot".to_owned(),
+                vec!["-e".to_owned(), "ssh".to_owned(), "ssh://host".to_owned()]
+            ))
+        );
+        assert_eq!(handler_command("  ", "ssh://host"), None);
+    }
+}
@@ -0,0 +1,155 @@
+use std::path::{Path, PathBuf};
+
+/// The timed lines of a `.lrc` file, sorted by time
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct Lyrics {
+    /// The time in microseconds, and the line
+    lines: Vec<(i64, String)>,
+}
+
+// NOTE: `mm:ss`, `mm:ss.xx` or `mm:ss:xx`, the fraction can have any number of digits
+fn parse_timestamp(tag: &str) -> Option<i64> {
+    let (minutes, rest) = tag.split_once(':')?;
+    let minutes: i64 = minutes.trim().parse().ok()?;
+    let (seconds, fraction) = match rest.split_once(['.', ':']) {
+        Some((seconds, fraction)) => (seconds, fraction),
+        None => (rest, ""),
+    };
+    let seconds: i64 = seconds.trim().parse().ok()?;
+    let mut micros = 0;
+    if !fraction.is_empty() {
+        let digits: String = fraction.chars().take(6).collect();
+        micros = digits.parse::<i64>().ok()? * 10_i64.pow(6 - digits.len() as u32);
+    }
+    Some((minutes * 60 + seconds) * 1_000_000 + micros)
+}
+
+impl Lyrics {
+    pub fn parse(content: &str) -> Self {
+        let mut offset = 0;
+        let mut lines = Vec::new();
+        for line in content.lines() {
+            let mut rest = line.trim();
+            let mut times = Vec::new();
+            while let Some(tag) = rest.strip_prefix('[') {
+                let Some((tag, after)) = tag.split_once(']') else {
+                    break;
+                };
+                rest = after;
+                if let Some(time) = parse_timestamp(tag) {
+                    times.push(time);
+                } else if let Some((key, value)) = tag.split_once(':')
+                    && key.trim().eq_ignore_ascii_case("offset")
+                {
+                    // NOTE: the offset is in milliseconds, a positive one shows the lines sooner
+                    offset = value.trim().parse::<i64>().unwrap_or(0) * 1000;
+                }
+            }
+            let text = rest.trim();
+            lines.extend(times.into_iter().map(|time| (time, text.to_owned())));
+        }
+        for (time, _) in lines.iter_mut() {
+            *time -= offset;
+        }
+        lines.sort_by_key(|(time, _)| *time);
+        Self { lines }
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.lines.is_empty()
+    }
+
+    /// The index of the line at the position in microseconds, None before the first line
+    fn index_at(&self, position: i64) -> Option<usize> {
+        self.lines
+            .partition_point(|(time, _)| *time <= position)
+            .checked_sub(1)
+    }
+
+    pub fn line_at(&self, position: i64) -> Option<&str> {
+        self.index_at(position)
+            .map(|index| self.lines[index].1.as_str())
+    }
+
+    pub fn next_line_at(&self, position: i64) -> Option<&str> {
+        let next = self.index_at(position).map_or(0, |index| index + 1);
+        self.lines.get(next).map(|(_, line)| line.as_str())
+    }
+}
+
+fn expand_home(path: &str) -> PathBuf {
+    match (path.strip_prefix("~/"), std::env::var("HOME")) {
+        (Some(path), Ok(home)) => Path::new(&home).join(path),
+        _ => PathBuf::from(path),
+    }
+}
+
+/// The `.lrc` file next to the track, or the one named `artist - title.lrc` in the directory
+fn find_lrc(url: Option<&str>, artist: &str, title: &str, dir: Option<&str>) -> Option<PathBuf> {
+    if let Some(path) = url
+        .and_then(|url| url::Url::parse(url).ok())
+        .and_then(|url| url.to_file_path().ok())
+        .map(|path| path.with_extension("lrc"))
+        .filter(|path| path.is_file())
+    {
+        return Some(path);
+    }
+    let name = format!("{artist} - {title}").replace('/', "_");
+    std::fs::read_dir(expand_home(dir?))
+        .ok()?
+        .flatten()
+        .map(|entry| entry.path())
+        .find(|path| {
+            path.extension()
+                .is_some_and(|extension| extension.eq_ignore_ascii_case("lrc"))
+                && path
+                    .file_stem()
+                    .and_then(|stem| stem.to_str())
+                    .is_some_and(|stem| stem.eq_ignore_ascii_case(&name))
+        })
+}
+
+pub async fn load_lyrics(
+    url: Option<String>,
+    artist: String,
+    title: String,
+    dir: Option<String>,
+) -> Option<Lyrics> {
+    let path = tokio::task::spawn_blocking(move || {
+        find_lrc(url.as_deref(), &artist, &title, dir.as_deref())
+    })
+    .await
+    .ok()??;
+    let content = tokio::fs::read_to_string(&path).await.ok()?;
+    let lyrics = Lyrics::parse(&content);
+    (!lyrics.is_empty()).then_some(lyrics)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn timestamps() {
+        assert_eq!(parse_timestamp("01:02.50"), Some(62_500_000));
+        assert_eq!(parse_timestamp("00:10:05"), Some(10_050_000));
+        assert_eq!(parse_timestamp("00:03.123"), Some(3_123_000));
+        assert_eq!(parse_timestamp("00:07"), Some(7_000_000));
+        assert_eq!(parse_timestamp("ar:someone"), None);
+    }
+
+    #[test]
+    fn lines_with_offset_and_repeats() {
+        let lyrics = Lyrics::parse(
+            "[ti:song]\n[offset:+500]\n[00:01.00]first\n[00:03.00][00:07.00]chorus\n[00:05.00]second\n",
+        );
+        assert_eq!(lyrics.line_at(0), None);
+        assert_eq!(lyrics.next_line_at(0), Some("first"));
+        assert_eq!(lyrics.line_at(600_000), Some("first"));
+        assert_eq!(lyrics.line_at(2_600_000), Some("chorus"));
+        assert_eq!(lyrics.line_at(4_600_000), Some("second"));
+        assert_eq!(lyrics.next_line_at(4_600_000), Some("chorus"));
+        assert_eq!(lyrics.line_at(9_000_000), Some("chorus"));
+        assert_eq!(lyrics.next_line_at(9_000_000), None);
+    }
+}
@@ -1,25 +1,34 @@
+use audio::DeviceKind;
 use iced::widget::markdown;
 use iced_zbus_notification::NotifyMessage;
 use launcher::{LaunchMessage, Launcher};
-use zbus_mpirs::ServiceInfo;
+use settings::MediaPolicy;
+use zbus_mpirs::{ConnectionState, ServiceInfo};
 
 use futures::channel::mpsc::Sender;
 use iced_aw::date_picker::Date;
 use iced_aw::time_picker::Time;
 use iced_layershell::to_layer_message;
 
+mod art;
+mod audio;
 mod aximer;
 mod config;
 mod dbusbackend;
+mod history;
 mod launcher;
+mod links;
 mod localization;
+mod lyrics;
 mod music_bar;
 mod notify;
+mod osd;
 mod settings;
 mod slider;
 mod wav_canvars;
 mod zbus_mpirs;
 
+use crate::dbusbackend::NowPlaying;
 use crate::music_bar::LalaMusicBar;
 use crate::notify::NotifyCommand;
 use crate::wav_canvars::PwEvent;
@@ -46,6 +55,12 @@ async fn main() -> Result<(), iced_layershell::Error> {
 pub enum LaLaInfo {
     Launcher,
     Notify(Box<NotifyUnitWidgetInfo>),
+    NotifyDetails,
+    LinkConfirm,
+    MediaPopup,
+    MixerPopup,
+    DevicesPopup,
+    Osd,
     HiddenInfo,
     RightPanel,
     ErrorHappened(iced::window::Id),
@@ -73,16 +88,51 @@ pub enum Message {
     RequestNext,
     RequestPause,
     RequestPlay,
+    RequestPlayPause,
+    SeekBy(i64),
+    CyclePlayer,
+    SeekChanged(f64),
+    SeekReleased,
+    ToggleShuffle,
+    CycleLoopStatus,
+    SetRate(f64),
+    PlayerVolumeChanged(f64),
+    PlayerVolumeReleased,
     // WAV CHAT
     Tick,
     Pw(PwEvent),
     // SOUNCD CONTROL
     UpdateData,
+    UpdateClock,
+    Shutdown,
     RequestMprisInfoUpdate,
-    MpirsInfoUpdate(Option<ServiceInfo>),
+    MpirsInfoUpdate(MprisState),
+    SelectPlayer(String),
+    PinPlayer(bool),
+    ToggleMediaPopup,
+    RaisePlayer,
+    QuitPlayer,
+    GoToTrack(String),
+    ActivatePlaylist(String),
+    LyricsLoaded((String, Option<lyrics::Lyrics>)),
+    HistoryStatsLoaded(history::HistoryStats),
+    ExportHistory,
+    HistoryExported(Option<std::path::PathBuf>),
     BalanceChanged(u8),
-    UpdateLeft(u8),
-    UpdateRight(u8),
+    UpdateChannel((usize, u8)),
+    VolumeChanged,
+    ToggleMute,
+    ToggleMixerPopup,
+    SetStreamVolume((u32, u8)),
+    ToggleStreamMute(u32),
+    ToggleDevicesPopup,
+    SetDefaultDevice((DeviceKind, String)),
+    SetProfile((u32, u32)),
+    SwitchRecentOutput,
+    SetInputLevel(u8),
+    ToggleInputMute,
+    ShowOsd(osd::Osd),
+    HideOsd(u64),
     SliderIndexNext,
     SliderIndexPre,
     // LAUNCHER
@@ -93,10 +143,22 @@ pub enum Message {
     LauncherInfo(LaunchMessage),
     Notify(NotifyMessage),
     RemoveNotify(u32),
-    InlineReply((u32, String)),
-    InlineReplyMsgUpdate((iced::window::Id, String)),
+    NotifyTimeout((u32, u64)),
+    DismissNotify(u32),
+    InvokeAction((u32, String)),
+    DismissNewestNotify,
+    DismissAllNotify,
+    InvokeNewestNotify,
+    ReplyNewestNotify,
+    FocusNextNotify,
+    NotifyKeyPressed((iced::window::Id, iced::keyboard::Key)),
+    ShowNotifyDetails(u32),
+    CloseNotifyDetails,
+    InlineReply(u32),
+    InlineReplyEdit((u32, iced::widget::text_editor::Action)),
     CloseErrorNotification(iced::window::Id),
     Ready(Sender<NotifyCommand>),
+    BackendReady(Sender<NowPlaying>),
     ReadyCheck(Sender<bool>),
     CheckId(u32),
     QuiteMode(bool),
@@ -104,8 +166,10 @@ pub enum Message {
     // BASE WAYLAND
     CheckOutput,
     // UTILS
-    #[allow(unused)]
+    CopyText(String),
     LinkClicked(markdown::Uri),
+    ConfirmLink(bool),
+    AllowLinkScheme,
     // CALENDAR AND DATE
     ToggleCalendar,
     CancelDate,
@@ -131,24 +195,78 @@ impl From<NotifyMessage> for Message {
     }
 }
 
-async fn get_metadata_initial() -> Option<ServiceInfo> {
+/// The player shown on the bar, and all the players which can be switched to
+#[derive(Debug, Clone, Default)]
+pub struct MprisState {
+    pub current: Option<ServiceInfo>,
+    pub players: Vec<ServiceInfo>,
+    /// The players which cannot be connected, by bus name
+    pub unavailable: Vec<(String, ConnectionState)>,
+}
+
+/// How the player shown on the bar is picked
+#[derive(Debug, Clone, Default)]
+pub struct PlayerSelection {
+    pub policy: MediaPolicy,
+    /// The player chosen in the switcher, it wins over the pinned one
+    pub selected: Option<String>,
+    /// The player shown now, it is kept until another one starts playing
+    pub current: Option<String>,
+}
+
+impl PlayerSelection {
+    fn select<'a>(&self, players: &'a [ServiceInfo]) -> Option<&'a ServiceInfo> {
+        let find = |path: &str| players.iter().find(|info| info.service_path() == path);
+        if let Some(selected) = self.selected.as_deref().and_then(find) {
+            return Some(selected);
+        }
+        if let Some(pinned) = players.iter().find(|info| self.policy.is_pinned(info)) {
+            return Some(pinned);
+        }
+        let current = self.current.as_deref().and_then(find);
+        if let Some(current) = current.filter(|info| info.playback_status == "Playing") {
+            return Some(current);
+        }
+        players
+            .iter()
+            .filter(|info| info.playback_status == "Playing")
+            .min_by_key(|info| self.policy.priority_of(info))
+            .or(current)
+            .or_else(|| {
+                players
+                    .iter()
+                    .min_by_key(|info| self.policy.priority_of(info))
+            })
+    }
+}
+
+async fn get_metadata_initial(selection: PlayerSelection) -> MprisState {
     zbus_mpirs::init_mpirs().await.ok();
-    get_metadata().await
+    get_metadata(selection).await
 }
 
-async fn get_metadata() -> Option<ServiceInfo> {
+async fn get_metadata(selection: PlayerSelection) -> MprisState {
     let infos = zbus_mpirs::MPIRS_CONNECTIONS.lock().await;
 
-    let alive_infos: Vec<&ServiceInfo> = infos
+    let players: Vec<ServiceInfo> = infos
         .iter()
-        .filter(|info| !info.metadata.xesam_title.is_empty())
+        .filter(|info| {
+            !info.metadata.xesam_title.is_empty() && !selection.policy.is_blacklisted(info)
+        })
+        .cloned()
         .collect();
+    drop(infos);
 
-    if let Some(playingserver) = alive_infos
-        .iter()
-        .find(|info| info.playback_status == "Playing")
-    {
-        return Some((*playingserver).clone());
+    let unavailable = zbus_mpirs::unavailable_players()
+        .await
+        .into_iter()
+        .filter(|(name, _)| !selection.policy.is_blacklisted_name(name))
+        .collect();
+
+    let current = selection.select(&players).cloned();
+    MprisState {
+        current,
+        players,
+        unavailable,
     }
-    alive_infos.first().cloned().cloned()
 }
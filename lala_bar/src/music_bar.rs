@@ -1,29 +1,38 @@
 use crate::ColorPickerResult;
 use crate::Launcher;
 use crate::RightPanelFilter;
+use crate::art::art_rgba;
+use crate::audio::{self, AppStream, Device, DeviceKind, Devices, RecentOutputs, Volume};
 use crate::config::*;
-use crate::dbusbackend;
+use crate::dbusbackend::{self, NowPlaying};
 use crate::get_metadata;
+use crate::history::{self, HistoryStats, ListenTracker};
+use crate::launcher;
 use crate::launcher::LaunchMessage;
+use crate::links::{link_scheme, open_link};
 use crate::localization::fl;
-use crate::notify::{NotifyCommand, NotifyUnitWidgetInfo};
+use crate::lyrics::{Lyrics, load_lyrics};
+use crate::notify::{
+    NotifyCommand, NotifyUnitWidgetInfo, ReplyThread, inline_reply_id, notify_details_view,
+};
+use crate::osd::Osd;
 use crate::settings::SettingsConfig;
 use crate::slider::SliderIndex;
 use crate::wav_canvars;
 use crate::wav_canvars::PwEvent;
 use crate::wav_canvars::WavState;
-use crate::zbus_mpirs::ServiceInfo;
-use crate::{LaLaInfo, Message, get_metadata_initial};
-use crate::{aximer, launcher};
-use chrono::{DateTime, Local};
+use crate::zbus_mpirs::{self, ConnectionState, LoopStatus, ServiceInfo, player_name};
+use crate::{LaLaInfo, Message, PlayerSelection, get_metadata_initial};
+use chrono::{DateTime, Local, Timelike};
 use fluent_bundle::FluentArgs;
 use futures::StreamExt;
 use futures::channel::mpsc::{Sender, channel};
 use futures::future::pending;
 use iced::widget::canvas;
+use iced::widget::operation::focus;
 use iced::widget::{
-    Space, button, checkbox, column, container, image, markdown, row, scrollable, slider, svg,
-    text, text_input,
+    Space, button, checkbox, column, container, image, markdown, progress_bar, row, scrollable,
+    slider, svg, text, text_editor, tooltip,
 };
 use iced::{Alignment, Element, Font, Length, Task as Command, Theme};
 use iced_aw::{date_picker::Date, helpers::date_picker, time_picker, time_picker::Time};
@@ -35,12 +44,68 @@ use iced_runtime::Action;
 use iced_runtime::window::Action as WindowAction;
 use iced_zbus_notification::MessageSenderDefault;
 use iced_zbus_notification::{
-    DEFAULT_ACTION, LaLaMako, NOTIFICATION_SERVICE_PATH, NotifyMessage, VersionInfo,
+    DEFAULT_ACTION, LaLaMako, NOTIFICATION_DELETED_BY_USER, NOTIFICATION_SERVICE_PATH, NotifyHint,
+    NotifyMessage, NotifyUnit, Urgency, VersionInfo,
 };
-use std::collections::HashMap;
+use std::collections::{HashMap, HashSet};
 
 use iced_layershell::build_pattern::daemon;
 
+/// The label of a channel slider, by the position of the channel
+fn channel_text(position: &str, percent: i64) -> String {
+    let mut args = FluentArgs::new();
+    args.set("percent", percent);
+    match position {
+        "FL" => fl!("balance-left", args),
+        "FR" => fl!("balance-right", args),
+        "MONO" => fl!("channel-mono", args),
+        _ => {
+            args.set("channel", position.to_owned());
+            fl!("channel-volume", args)
+        }
+    }
+}
+
+// NOTE: the clock only shows the minutes, so wake up at the start of every minute
+fn minute_ticks() -> iced::Subscription<()> {
+    iced::Subscription::run(|| {
+        iced::stream::channel(1, |mut output: Sender<()>| async move {
+            use iced::futures::sink::SinkExt;
+            loop {
+                let now = Local::now();
+                let elapsed = now.second() as u64 * 1000 + now.timestamp_subsec_millis() as u64;
+                let wait = 60_000u64.saturating_sub(elapsed);
+                tokio::time::sleep(std::time::Duration::from_millis(wait)).await;
+                if output.send(()).await.is_err() {
+                    break;
+                }
+            }
+        })
+    })
+}
+
+// NOTE: the bar is usually stopped by a signal, it is caught to save what is not saved yet
+fn shutdown_signals() -> iced::Subscription<()> {
+    iced::Subscription::run(|| {
+        iced::stream::channel(1, |mut output: Sender<()>| async move {
+            use iced::futures::sink::SinkExt;
+            use tokio::signal::unix::{SignalKind, signal};
+            let (Ok(mut interrupt), Ok(mut terminate)) = (
+                signal(SignalKind::interrupt()),
+                signal(SignalKind::terminate()),
+            ) else {
+                tracing::warn!("cannot listen to the shutdown signals");
+                return;
+            };
+            tokio::select! {
+                _ = interrupt.recv() => {}
+                _ = terminate.recv() => {}
+            }
+            output.send(()).await.ok();
+        })
+    })
+}
+
 pub fn run_lalabar() -> iced_layershell::Result {
     daemon(
         LalaMusicBar::new,
@@ -65,11 +130,22 @@ pub fn run_lalabar() -> iced_layershell::Result {
 
 pub struct LalaMusicBar {
     pub(crate) service_data: Option<ServiceInfo>,
+    players: Vec<ServiceInfo>,
+    unavailable_players: Vec<(String, ConnectionState)>,
+    selected_player: Option<String>,
     pub(crate) left: i64,
     right: i64,
-    left_text: String,
-    right_text: String,
+    // NOTE: the label of every channel slider, like "Left: 50%"
+    channel_texts: Vec<String>,
     balance_text: String,
+    muted: bool,
+    streams: Vec<AppStream>,
+    // NOTE: the volume of the default output, to tell when it changes
+    output_volume: Option<Volume>,
+    input: Option<Volume>,
+    captures: Vec<AppStream>,
+    devices: Devices,
+    recent_outputs: RecentOutputs,
     bar_index: SliderIndex,
     launcher: Option<launcher::Launcher>,
     launcherid: Option<iced::window::Id>,
@@ -80,21 +156,117 @@ pub struct LalaMusicBar {
     showned_notifications: HashMap<iced::window::Id, u32>,
     cached_notifications: HashMap<iced::window::Id, NotifyUnitWidgetInfo>,
     cached_hidden_notifications: Vec<NotifyUnitWidgetInfo>,
+    focused_notification: Option<iced::window::Id>,
+    // NOTE: drafts are kept by notification id, so they survive the popup being closed
+    reply_drafts: HashMap<u32, text_editor::Content>,
+    // NOTE: the popups grown by their drafts, they should be shrunk back later
+    grown_popups: HashSet<iced::window::Id>,
+    reply_threads: Vec<ReplyThread>,
+    // NOTE: banners are the popups outside of the stack, they are shown on the top center of
+    // the outputs, and are not counted by the hidden list
+    banner_notifications: HashMap<u32, NotifyUnitWidgetInfo>,
+    banner_windows: HashMap<iced::window::Id, (u32, Option<String>)>,
     sender: Option<Sender<NotifyCommand>>,
+    backend_sender: Option<Sender<NowPlaying>>,
+    // NOTE: the last NowPlaying sent to the backend, it is only sent again when it changes
+    now_playing: NowPlaying,
     check_sender: Option<Sender<bool>>,
     quite_mode: bool,
     datetime: DateTime<Local>,
     calendar_id: Option<iced::window::Id>,
+    details_id: Option<iced::window::Id>,
+    details_notification: Option<NotifyUnit>,
+    link_confirm_id: Option<iced::window::Id>,
+    media_popup_id: Option<iced::window::Id>,
+    // NOTE: the serial of the last timeout of every notification, a replaced notification gets
+    // a new one, so the timeout of the old content is ignored
+    notify_timeouts: HashMap<u32, u64>,
+    notify_serial: u64,
+    mixer_popup_id: Option<iced::window::Id>,
+    devices_popup_id: Option<iced::window::Id>,
+    osd_id: Option<iced::window::Id>,
+    osd: Option<Osd>,
+    // NOTE: counts the OSDs shown, only the timer of the last one hides it
+    osd_serial: u64,
+    // NOTE: the position in seconds the seek bar is dragged to, applied on release
+    seek_preview: Option<f64>,
+    // NOTE: the same for the volume slider of the player
+    player_volume_preview: Option<f64>,
+    // NOTE: the lyrics of the track the key points to, None while loading or if there is none
+    lyrics: Option<(String, Option<Lyrics>)>,
+    listen_tracker: ListenTracker,
+    history_stats: Option<HistoryStats>,
+    // NOTE: the file the history was exported to, shown in the media popup
+    history_export: Option<String>,
+    // NOTE: the track in the track notification, and if its art is shown
+    media_announcement: Option<(String, bool)>,
+    pending_link: Option<String>,
     date: Date,
     time: Time,
     time_picker_id: Option<iced::window::Id>,
     right_filter: RightPanelFilter,
 
-    bar_settings: SettingsConfig,
+    pub(crate) bar_settings: SettingsConfig,
 
     wav_data: wav_canvars::WavState,
 }
 
+/// Identify the track shown, it changes when the player or the track changes
+fn track_key(data: &ServiceInfo) -> String {
+    format!(
+        "{}\n{}\n{}",
+        data.service_path(),
+        data.metadata.track_id(),
+        data.metadata.xesam_title
+    )
+}
+
+async fn media_notification(data: ServiceInfo) -> NotifyUnit {
+    let metadata = &data.metadata;
+    let mut actions = Vec::new();
+    if data.can_pause {
+        actions.extend(["pause".to_owned(), fl!("pause")]);
+    }
+    if data.can_go_next {
+        actions.extend(["next".to_owned(), fl!("next")]);
+    }
+    let mut hint = NotifyHint::new(Urgency::Low, None);
+    if let Some(handle) = metadata
+        .mpris_image
+        .clone()
+        .filter(|_| !metadata.art_is_pending())
+        && let Some((width, height, pixels)) = art_rgba(handle, MEDIA_NOTIFICATION_ART_SIZE).await
+    {
+        hint = hint.with_rgba_image(width as i32, height as i32, pixels);
+    }
+    let mut body = metadata.artists().join(", ");
+    if !metadata.album().is_empty() {
+        body = format!("{body}\n\n{}", metadata.album());
+    }
+    NotifyUnit {
+        app_name: data.identity.clone(),
+        id: MEDIA_NOTIFICATION_ID,
+        icon: String::new(),
+        summery: metadata.xesam_title.clone(),
+        body,
+        actions,
+        timeout: MEDIA_NOTIFICATION_TIMEOUT,
+        hint,
+        timestamp: std::time::SystemTime::now(),
+    }
+}
+
+/// Format the microseconds as `m:ss`, or `h:mm:ss` if it is longer than an hour
+fn format_position(micros: i64) -> String {
+    let seconds = micros.max(0) / 1_000_000;
+    let (hours, minutes, seconds) = (seconds / 3600, seconds / 60 % 60, seconds % 60);
+    if hours > 0 {
+        format!("{hours}:{minutes:02}:{seconds:02}")
+    } else {
+        format!("{minutes}:{seconds:02}")
+    }
+}
+
 async fn color_pick() -> ColorPickerResult {
     use ashpd::desktop::Color;
     let Ok(response) = Color::pick().send().await else {
@@ -111,6 +283,299 @@ async fn color_pick() -> ColorPickerResult {
 }
 
 impl LalaMusicBar {
+    fn player_selection(&self) -> PlayerSelection {
+        PlayerSelection {
+            policy: self.bar_settings.media.clone(),
+            selected: self.selected_player.clone(),
+            current: self
+                .service_data
+                .as_ref()
+                .map(|data| data.service_path().to_owned()),
+        }
+    }
+
+    /// The players to switch to, and the button to pin the shown one
+    fn player_switcher<'a>(&'a self, data: &ServiceInfo) -> Element<'a, Message> {
+        let players = self.players.iter().map(|info| {
+            let name = if info.state == ConnectionState::Degraded {
+                format!("{} ⚠", info.identity)
+            } else {
+                info.identity.clone()
+            };
+            button(text(name).shaping(text::Shaping::Advanced))
+                .style(if info.service_path() == data.service_path() {
+                    button::primary
+                } else {
+                    button::secondary
+                })
+                .on_press(Message::SelectPlayer(info.service_path().to_owned()))
+                .into()
+        });
+        let pinned = self.bar_settings.media.is_pinned(data);
+        row![
+            scrollable(row(players).spacing(5.))
+                .direction(scrollable::Direction::Horizontal(
+                    scrollable::Scrollbar::new().width(2.).scroller_width(2.)
+                ))
+                .width(Length::Fill),
+            button(text(if pinned { fl!("unpin") } else { fl!("pin") }))
+                .style(if pinned {
+                    button::primary
+                } else {
+                    button::secondary
+                })
+                .on_press(Message::PinPlayer(!pinned)),
+        ]
+        .spacing(5.)
+        .into()
+    }
+
+    /// Previous, play or pause, and next
+    fn transport_buttons<'a>(&self, data: &ServiceInfo) -> Element<'a, Message> {
+        let can_play = data.can_play;
+        let can_pause = data.can_pause;
+        let can_go_next = data.can_go_next;
+        let can_go_pre = data.can_go_previous;
+        let mut button_pre = button(svg(GO_PREVIOUS_HANDLE.clone()).width(25.).height(25.))
+            .width(30.)
+            .height(30.);
+        if can_go_pre {
+            button_pre = button_pre.on_press(Message::RequestPre);
+        }
+        let mut button_next = button(svg(GO_NEXT_HANDLE.clone()).width(25.).height(25.))
+            .width(30.)
+            .height(30.);
+        if can_go_next {
+            button_next = button_next.on_press(Message::RequestNext);
+        }
+        let button_play = if data.playback_status == "Playing" {
+            let mut btn = button(svg(PAUSE_HANDLE.clone()).width(25.).height(25.))
+                .width(30.)
+                .height(30.);
+            if can_pause {
+                btn = btn.on_press(Message::RequestPause);
+            }
+            btn
+        } else {
+            let mut btn = button(svg(PLAY_HANDLE.clone()).width(25.).height(25.))
+                .width(30.)
+                .height(30.);
+            if can_play {
+                btn = btn.on_press(Message::RequestPlay);
+            }
+            btn
+        };
+        row![button_pre, button_play, button_next].spacing(5).into()
+    }
+
+    fn sync_now_playing(&mut self) {
+        let now_playing = NowPlaying::new(self.service_data.as_ref());
+        if now_playing == self.now_playing {
+            return;
+        }
+        if let Some(sender) = &mut self.backend_sender
+            && sender.try_send(now_playing.clone()).is_ok()
+        {
+            self.now_playing = now_playing;
+        }
+    }
+
+    /// Notify the new track once it starts playing, the notification is shown again once the art
+    /// of the track is loaded
+    fn announce_track(&mut self) -> Command<Message> {
+        let Some(data) = self
+            .service_data
+            .as_ref()
+            .filter(|_| self.bar_settings.media.track_notification)
+        else {
+            return Command::none();
+        };
+        let key = track_key(data);
+        let art_ready = !data.metadata.art_is_pending();
+        match &self.media_announcement {
+            Some((announced, art_shown)) if *announced == key => {
+                let shown = self.notifications.contains_key(&MEDIA_NOTIFICATION_ID)
+                    || self
+                        .banner_notifications
+                        .contains_key(&MEDIA_NOTIFICATION_ID);
+                if *art_shown || !art_ready || !shown {
+                    return Command::none();
+                }
+            }
+            _ if data.playback_status != "Playing" => return Command::none(),
+            _ => {}
+        }
+        self.media_announcement = Some((key, art_ready));
+        if self.quite_mode {
+            return Command::none();
+        }
+        Command::perform(media_notification(data.clone()), |unit| {
+            Message::Notify(NotifyMessage::UnitAdd(Box::new(unit)))
+        })
+    }
+
+    /// Load the lyrics once the shown track changes
+    fn sync_lyrics(&mut self) -> Command<Message> {
+        let policy = &self.bar_settings.lyrics;
+        let Some(data) = self.service_data.as_ref().filter(|_| policy.enabled()) else {
+            self.lyrics.take();
+            return Command::none();
+        };
+        let metadata = &data.metadata;
+        let key = track_key(data);
+        if self
+            .lyrics
+            .as_ref()
+            .is_some_and(|(loaded, _)| *loaded == key)
+        {
+            return Command::none();
+        }
+        self.lyrics = Some((key.clone(), None));
+        Command::perform(
+            load_lyrics(
+                metadata.url().map(str::to_owned),
+                metadata.artists().join(", "),
+                metadata.xesam_title.clone(),
+                policy.directory.clone(),
+            ),
+            move |lyrics| Message::LyricsLoaded((key.clone(), lyrics)),
+        )
+    }
+
+    /// The lyric line at the current position, and the next one
+    fn lyric_lines<'a>(&'a self, data: &ServiceInfo) -> Option<(&'a str, Option<&'a str>)> {
+        let (_, lyrics) = self.lyrics.as_ref()?;
+        let lyrics = lyrics.as_ref()?;
+        let position = data.position();
+        Some((
+            lyrics.line_at(position).unwrap_or_default(),
+            lyrics.next_line_at(position),
+        ))
+    }
+
+    // NOTE: run the action on the shown player, then refresh the state
+    fn request_player<F>(
+        &self,
+        action: impl FnOnce(ServiceInfo) -> F + Send + 'static,
+    ) -> Command<Message>
+    where
+        F: Future<Output = zbus::Result<()>> + Send + 'static,
+    {
+        let Some(data) = self.service_data.clone() else {
+            return Command::none();
+        };
+        let selection = self.player_selection();
+        Command::perform(
+            async move {
+                action(data).await.ok();
+                get_metadata(selection).await
+            },
+            Message::MpirsInfoUpdate,
+        )
+    }
+
+    /// Shuffle, loop, rate and volume of the player, disabled if the player cannot be controlled
+    fn media_controls<'a>(&self, data: &ServiceInfo) -> Element<'a, Message> {
+        let can_control = data.can_control;
+        let toggle_style = |enabled: bool| {
+            if enabled {
+                button::primary
+            } else {
+                button::secondary
+            }
+        };
+        let mut controls = row![].spacing(5.).align_y(Alignment::Center);
+        if let Some(shuffle) = data.shuffle {
+            controls = controls.push(
+                button(text(fl!("shuffle")))
+                    .style(toggle_style(shuffle))
+                    .on_press_maybe(can_control.then_some(Message::ToggleShuffle)),
+            );
+        }
+        if let Some(status) = data.loop_status {
+            let label = match status {
+                LoopStatus::None => fl!("loop-none"),
+                LoopStatus::Track => fl!("loop-track"),
+                LoopStatus::Playlist => fl!("loop-playlist"),
+            };
+            controls = controls.push(
+                button(text(label))
+                    .style(toggle_style(status != LoopStatus::None))
+                    .on_press_maybe(can_control.then_some(Message::CycleLoopStatus)),
+            );
+        }
+        // NOTE: the rate is changed by steps, so the player is not flooded while dragging
+        if data.minimum_rate < data.maximum_rate {
+            let rate = data.rate;
+            controls = controls.push(Space::new().width(Length::Fill)).push(
+                row![
+                    button(text("-")).on_press_maybe(
+                        (can_control && rate > data.minimum_rate)
+                            .then_some(Message::SetRate(rate - RATE_STEP))
+                    ),
+                    container(text(format!("{rate:.2}x")).size(12)).padding([0, 5]),
+                    button(text("+")).on_press_maybe(
+                        (can_control && rate < data.maximum_rate)
+                            .then_some(Message::SetRate(rate + RATE_STEP))
+                    ),
+                ]
+                .align_y(Alignment::Center),
+            );
+        }
+        let mut view = column![controls].spacing(5.);
+        if let Some(volume) = data.volume {
+            let volume = self.player_volume_preview.unwrap_or(volume * 100.);
+            let volume_bar: Element<Message> = if can_control {
+                slider(0.0..=100.0, volume, Message::PlayerVolumeChanged)
+                    .on_release(Message::PlayerVolumeReleased)
+                    .into()
+            } else {
+                progress_bar(0.0..=100.0, volume as f32).girth(4.).into()
+            };
+            view = view.push(
+                row![
+                    text(fl!("player-volume")).size(12),
+                    volume_bar,
+                    text(format!("{volume:.0}%")).size(12),
+                ]
+                .spacing(5.)
+                .align_y(Alignment::Center),
+            );
+        }
+        view.into()
+    }
+
+    /// The elapsed time, the seek bar and the length of the track
+    fn seek_bar<'a>(&self, data: &ServiceInfo) -> Element<'a, Message> {
+        let position = data.position();
+        let Some(length) = data.metadata.mpris_length.filter(|length| *length > 0) else {
+            return text(format_position(position)).size(12).into();
+        };
+        let total = length as f64 / 1_000_000.;
+        let current = self
+            .seek_preview
+            .unwrap_or(position as f64 / 1_000_000.)
+            .min(total);
+        let bar: Element<Message> = if data.can_seek {
+            slider(0.0..=total, current, Message::SeekChanged)
+                .on_release(Message::SeekReleased)
+                .step(1.)
+                .into()
+        } else {
+            progress_bar(0.0..=total as f32, current as f32)
+                .girth(4.)
+                .into()
+        };
+        row![
+            text(format_position((current * 1_000_000.) as i64)).size(12),
+            bar,
+            text(format_position(length)).size(12),
+        ]
+        .spacing(5.)
+        .align_y(Alignment::Center)
+        .into()
+    }
+
     pub fn date_widget(&'_ self) -> Element<'_, Message> {
         let date = self.datetime.date_naive();
         let dateday = date.format("%m-%d").to_string();
@@ -159,35 +624,180 @@ impl LalaMusicBar {
             .unwrap()
     }
 
-    fn update_balance(&mut self) {
-        self.left = aximer::get_left().unwrap_or(0);
-        self.right = aximer::get_right().unwrap_or(0);
-        let mut args = FluentArgs::new();
-        args.set("percent", self.left);
-        self.left_text = fl!("balance-left", args);
+    fn update_clock(&mut self) {
+        self.datetime = Local::now();
+        self.date = self.datetime.date_naive().into();
+        self.time = self.datetime.time().into();
+    }
 
-        let mut args = FluentArgs::new();
-        args.set("percent", self.right);
-        self.right_text = fl!("balance-right", args);
+    /// Whether the volume of the default output changed since the last update
+    fn update_balance(&mut self) -> bool {
+        let volume = audio::backend().volume();
+        let current = volume.clone().unwrap_or_default();
+        self.left = current.left();
+        self.right = current.right();
+        self.muted = current.mute;
+        let previous = std::mem::replace(&mut self.output_volume, volume);
+        let previous_output = self.devices.default_output.clone();
+        self.streams = audio::backend().streams();
+        self.input = audio::backend().input_volume();
+        let show_own = self.bar_settings.microphone.show_spectrum_capture;
+        self.captures = audio::backend()
+            .captures()
+            .into_iter()
+            .filter(|stream| show_own || !stream.own)
+            .collect();
+        self.devices = audio::backend().devices();
+        self.recent_outputs
+            .update(self.devices.default_output.as_deref());
+        self.update_balance_text();
+        // NOTE: an output which is plugged in or gone, or a restarted daemon, is not a change of
+        // the volume
+        previous.is_some()
+            && self.output_volume.is_some()
+            && previous != self.output_volume
+            && previous_output == self.devices.default_output
+    }
+
+    fn update_balance_text(&mut self) {
+        self.channel_texts = self
+            .output_volume
+            .as_ref()
+            .map(|volume| {
+                volume
+                    .channels
+                    .iter()
+                    .zip(&volume.positions)
+                    .map(|(percent, position)| channel_text(position, *percent))
+                    .collect()
+            })
+            .unwrap_or_default();
+        self.bar_index = self.bar_index.fit(self.channel_texts.len());
 
         let mut args = FluentArgs::new();
         args.set("percent", self.balance_percent());
         self.balance_text = fl!("balance-combined", args);
     }
 
-    fn set_balance(&mut self, balance: u8) {
-        self.update_balance();
+    fn volume_osd(&mut self) -> Command<Message> {
+        if !self.bar_settings.osd.volume {
+            return Command::none();
+        }
+        let Some(volume) = &self.output_volume else {
+            return Command::none();
+        };
+        self.show_osd(Osd::volume(volume))
+    }
+
+    fn show_osd(&mut self, osd: Osd) -> Command<Message> {
+        self.osd = Some(osd);
+        self.osd_serial += 1;
+        let serial = self.osd_serial;
+        let timeout = self.bar_settings.osd.timeout;
+        let hide = Command::perform(
+            async move { tokio::time::sleep(std::time::Duration::from_millis(timeout)).await },
+            move |_| Message::HideOsd(serial),
+        );
+        if self.osd_id.is_some() {
+            return hide;
+        }
+        let id = iced::window::Id::unique();
+        self.set_id_info(id, LaLaInfo::Osd);
+        Command::batch([
+            hide,
+            Command::done(Message::NewLayerShell {
+                settings: NewLayerShellSettings {
+                    size: Some((300, 80)),
+                    exclusive_zone: None,
+                    // NOTE: without anchors, the surface is put in the center of the output
+                    anchor: Anchor::empty(),
+                    layer: Layer::Overlay,
+                    margin: None,
+                    keyboard_interactivity: KeyboardInteractivity::None,
+                    output_option: OutputOption::LastOutput,
+                    events_transparent: true,
+                    ..Default::default()
+                },
+                id,
+            }),
+        ])
+    }
+
+    fn set_balance(&mut self, balance: u8) -> Command<Message> {
         let total = self.left + self.right;
         self.right = total * balance as i64 / 100;
         self.left = total - self.right;
-        aximer::set_left(self.left);
-        aximer::set_right(self.right);
+        self.set_output_channels(&[self.left, self.right])
+    }
+
+    // NOTE: the mixer only reports the new volume a moment later, so the requested one is shown
+    // until then
+    fn set_output_channels(&mut self, channels: &[i64]) -> Command<Message> {
+        audio::backend().set_channels(channels);
+        if let Some(volume) = &mut self.output_volume {
+            for (channel, percent) in volume.channels.iter_mut().zip(channels) {
+                *channel = *percent;
+            }
+            self.left = volume.left();
+            self.right = volume.right();
+        }
+        self.update_balance_text();
+        self.volume_osd()
+    }
+
+    fn set_output_mute(&mut self, mute: bool) -> Command<Message> {
+        audio::backend().set_mute(mute);
+        self.muted = mute;
+        if let Some(volume) = &mut self.output_volume {
+            volume.mute = mute;
+        }
+        self.volume_osd()
     }
 }
 
 impl LalaMusicBar {
+    // NOTE: the calendar, the time picker and the audio popups are all opened in the bottom right
+    // corner, so only one of them is shown at a time
+    fn close_corner_popups(&self) -> Command<Message> {
+        Command::batch(
+            [
+                self.calendar_id,
+                self.time_picker_id,
+                self.mixer_popup_id,
+                self.devices_popup_id,
+            ]
+            .into_iter()
+            .flatten()
+            .map(|id| iced_runtime::task::effect(Action::Window(WindowAction::Close(id)))),
+        )
+    }
+
+    /// Start the timeout of the notification again, the earlier ones are ignored
+    fn restart_notify_timeout(&mut self, notify: &NotifyUnit) -> Command<Message> {
+        let policy = self.bar_settings.notification_policy(notify.urgency());
+        if notify.timeout == -1 || policy.sticky {
+            self.notify_timeouts.remove(&notify.id);
+            return Command::none();
+        }
+        self.notify_serial += 1;
+        let serial = self.notify_serial;
+        let id = notify.id;
+        let timeout = notify.timeout as u64;
+        self.notify_timeouts.insert(id, serial);
+        Command::perform(
+            async move { tokio::time::sleep(std::time::Duration::from_secs(timeout)).await },
+            move |_| Message::NotifyTimeout((id, serial)),
+        )
+    }
+
     // NOTE: not use signal to invoke remove, but use a common function
     fn remove_notify(&mut self, removed_id: u32) -> Command<Message> {
+        self.notify_timeouts.remove(&removed_id);
+        // NOTE: a draft is kept as long as its notification, in a popup or in the hidden list
+        self.reply_drafts.remove(&removed_id);
+        if self.banner_notifications.contains_key(&removed_id) {
+            return self.remove_banner(removed_id);
+        }
         let mut commands = vec![];
         let removed_counter = if let Some(removed_unit) = self.notifications.get_mut(&removed_id) {
             // NOTE: marked it as removable, but not now
@@ -266,6 +876,235 @@ impl LalaMusicBar {
 
         self.update_hidden_notification();
 
+        commands.push(self.sync_popup_layers());
+        commands.push(self.sync_popup_sizes());
+
+        Command::batch(commands)
+    }
+
+    fn popup_layer(&self, notify_id: u32) -> Layer {
+        self.notifications
+            .get(&notify_id)
+            .map(|info| {
+                self.bar_settings
+                    .notification_policy(info.unit.urgency())
+                    .layer()
+            })
+            .unwrap_or(Layer::Top)
+    }
+
+    // NOTE: popups are reused by other notifications when the stack moves, so their layer
+    // should follow the notification shown inside
+    fn sync_popup_layers(&self) -> Command<Message> {
+        let policy = &self.bar_settings.notification;
+        if policy.low.layer() == policy.normal.layer()
+            && policy.normal.layer() == policy.critical.layer()
+        {
+            return Command::none();
+        }
+        Command::batch(
+            self.showned_notifications
+                .iter()
+                .filter(|(id, _)| !self.cached_notifications.contains_key(id))
+                .map(|(id, nid)| {
+                    Command::done(Message::LayerChange {
+                        id: *id,
+                        layer: self.popup_layer(*nid),
+                    })
+                }),
+        )
+    }
+
+    fn open_banner(&mut self, info: NotifyUnitWidgetInfo) -> Command<Message> {
+        let urgency = info.unit.urgency();
+        let layer = self.bar_settings.notification_policy(urgency).layer();
+        let mut outputs: Vec<Option<String>> = self
+            .bar_settings
+            .notification
+            .banner_outputs(urgency)
+            .iter()
+            .cloned()
+            .map(Some)
+            .collect();
+        if outputs.is_empty() {
+            outputs.push(None);
+        }
+        let mut commands = vec![];
+        for output in outputs {
+            let index = self
+                .banner_windows
+                .values()
+                .filter(|(_, boutput)| *boutput == output)
+                .count() as i32;
+            let id = iced::window::Id::unique();
+            self.banner_windows
+                .insert(id, (info.unit.id, output.clone()));
+            commands.push(Command::done(Message::NewLayerShell {
+                settings: NewLayerShellSettings {
+                    size: Some((300, 130)),
+                    exclusive_zone: None,
+                    anchor: Anchor::Top,
+                    layer,
+                    margin: Some((BEGINNING_UP_MARGIN + index * UNIT_MARGIN, 10, 10, 10)),
+                    keyboard_interactivity: KeyboardInteractivity::OnDemand,
+                    output_option: output
+                        .map(OutputOption::OutputName)
+                        .unwrap_or(OutputOption::None),
+                    events_transparent: false,
+                    ..Default::default()
+                },
+                id,
+            }));
+        }
+        self.banner_notifications.insert(info.unit.id, info);
+        Command::batch(commands)
+    }
+
+    fn remove_banner(&mut self, removed_id: u32) -> Command<Message> {
+        let Some(info) = self.banner_notifications.get_mut(&removed_id) else {
+            return Command::none();
+        };
+        // NOTE: the data is removed when all of its windows are closed
+        info.to_delete = true;
+        let mut commands: Vec<Command<Message>> = self
+            .banner_windows
+            .iter()
+            .filter(|(_, (nid, _))| *nid == removed_id)
+            .map(|(id, _)| iced_runtime::task::effect(Action::Window(WindowAction::Close(*id))))
+            .collect();
+
+        let mut left_banners: Vec<(&iced::window::Id, &(u32, Option<String>))> = self
+            .banner_windows
+            .iter()
+            .filter(|(_, (nid, _))| *nid != removed_id)
+            .collect();
+        left_banners.sort_by_key(|(_, (nid, _))| *nid);
+        let mut indexes: HashMap<&Option<String>, i32> = HashMap::new();
+        for (id, (_, output)) in left_banners {
+            let index = indexes.entry(output).or_default();
+            commands.push(Command::done(Message::MarginChange {
+                id: *id,
+                margin: (BEGINNING_UP_MARGIN + *index * UNIT_MARGIN, 10, 10, 10),
+            }));
+            *index += 1;
+        }
+        Command::batch(commands)
+    }
+
+    fn notification_info(&self, notify_id: u32) -> Option<&NotifyUnitWidgetInfo> {
+        self.notifications
+            .get(&notify_id)
+            .or_else(|| self.banner_notifications.get(&notify_id))
+    }
+
+    fn newest_notification(&self) -> Option<u32> {
+        self.notifications
+            .iter()
+            .filter(|(_, info)| !info.to_delete)
+            .min_by_key(|(_, info)| info.counter)
+            .map(|(id, _)| *id)
+    }
+
+    // NOTE: the newest popup comes first
+    fn visible_notifications(&self) -> Vec<(iced::window::Id, u32)> {
+        let mut visible: Vec<(iced::window::Id, u32)> = self
+            .showned_notifications
+            .iter()
+            .filter(|(id, _)| !self.cached_notifications.contains_key(id))
+            .map(|(id, nid)| (*id, *nid))
+            .collect();
+        visible.sort_by(|(_, a), (_, b)| b.cmp(a));
+        visible
+    }
+
+    fn open_link(&self, link: String) -> Command<Message> {
+        let policy = &self.bar_settings.links;
+        let handler = link_scheme(&link)
+            .and_then(|scheme| policy.handler(&scheme))
+            .map(ToOwned::to_owned);
+        Command::future(open_link(link, handler, policy.use_portal)).discard()
+    }
+
+    fn reply_extra_height(&self, notify_id: u32) -> u32 {
+        self.reply_drafts
+            .get(&notify_id)
+            .map(|draft| (draft.line_count().clamp(1, MAX_REPLY_LINES) - 1) as u32)
+            .unwrap_or(0)
+            * REPLY_LINE_HEIGHT
+    }
+
+    // NOTE: a popup grows with its reply draft, so the ones below it should move down
+    fn sync_popup_sizes(&mut self) -> Command<Message> {
+        let mut commands = vec![];
+        let mut offset = 0;
+        for (id, nid) in self.visible_notifications() {
+            let Some(upper) = self.notifications.get(&nid).map(|info| info.upper) else {
+                continue;
+            };
+            let extra = self.reply_extra_height(nid);
+            if offset == 0 && extra == 0 && !self.grown_popups.remove(&id) {
+                continue;
+            }
+            if offset != 0 || extra != 0 {
+                self.grown_popups.insert(id);
+            }
+            commands.push(Command::done(Message::MarginChange {
+                id,
+                margin: (upper + offset as i32, 10, 10, 10),
+            }));
+            commands.push(Command::done(Message::SizeChange {
+                id,
+                size: (300, NOTIFICATION_POPUP_HEIGHT + extra),
+            }));
+            offset += extra;
+        }
+        Command::batch(commands)
+    }
+
+    fn record_reply(&mut self, notify_id: u32, text: String) {
+        let Some(unit) = self
+            .notification_info(notify_id)
+            .map(|info| info.unit.clone())
+        else {
+            return;
+        };
+        let mut thread = match self
+            .reply_threads
+            .iter()
+            .position(|thread| thread.is_conversation_of(&unit))
+        {
+            Some(index) => self.reply_threads.remove(index),
+            None => ReplyThread {
+                app_name: unit.app_name,
+                summary: unit.summery,
+                replies: vec![],
+            },
+        };
+        thread.replies.push((Local::now(), text));
+        // NOTE: the latest conversation is on the top
+        self.reply_threads.insert(0, thread);
+        self.reply_threads.truncate(MAX_REPLY_THREADS);
+    }
+
+    // NOTE: only one popup can hold the keyboard, the others go back to OnDemand
+    fn focus_notification(&mut self, id: Option<iced::window::Id>) -> Command<Message> {
+        if self.focused_notification == id {
+            return Command::none();
+        }
+        let mut commands = vec![];
+        if let Some(old_id) = self.focused_notification.take() {
+            commands.push(Command::done(Message::KeyboardInteractivityChange {
+                id: old_id,
+                keyboard_interactivity: KeyboardInteractivity::OnDemand,
+            }));
+        }
+        if let Some(id) = id {
+            self.focused_notification = Some(id);
+            commands.push(Command::done(Message::KeyboardInteractivityChange {
+                id,
+                keyboard_interactivity: KeyboardInteractivity::Exclusive,
+            }));
+        }
         Command::batch(commands)
     }
 }
@@ -290,36 +1129,113 @@ impl LalaMusicBar {
         .align_y(Alignment::Center)
         .into()
     }
-    fn left_bar(&'_ self) -> Element<'_, Message> {
+    fn channel_bar(&'_ self, index: usize) -> Element<'_, Message> {
+        let percent = self
+            .output_volume
+            .as_ref()
+            .and_then(|volume| volume.channels.get(index))
+            .copied()
+            .unwrap_or(0)
+            .clamp(0, 100);
         row![
             button("<").on_press(Message::SliderIndexPre),
-            text(&self.left_text),
-            slider(0..=100, self.left as u8, Message::UpdateLeft),
+            text(self.channel_texts.get(index).cloned().unwrap_or_default()),
+            slider(0..=100, percent as u8, move |percent| {
+                Message::UpdateChannel((index, percent))
+            }),
             button(">").on_press(Message::SliderIndexNext)
         ]
         .spacing(5.)
         .align_y(Alignment::Center)
         .into()
     }
-    fn right_bar(&'_ self) -> Element<'_, Message> {
+
+    fn microphone_widget(&self) -> Element<'_, Message> {
+        let mut view = row![].spacing(5.).align_y(Alignment::Center);
+        if !self.captures.is_empty() {
+            let apps: Vec<&str> = self
+                .captures
+                .iter()
+                .map(|stream| stream.name.as_str())
+                .collect();
+            let mut args = FluentArgs::new();
+            args.set("apps", apps.join(", "));
+            view = view.push(tooltip(
+                text("●").style(text::danger),
+                container(text(fl!("capturing", args)).size(12))
+                    .padding(5.)
+                    .style(container::rounded_box),
+                tooltip::Position::Top,
+            ));
+        }
+        if let Some(input) = &self.input {
+            let level = input
+                .channels
+                .iter()
+                .copied()
+                .max()
+                .unwrap_or(0)
+                .clamp(0, 100) as u8;
+            view = view.push(
+                button(text(if input.mute {
+                    fl!("mic-muted")
+                } else {
+                    fl!("mic")
+                }))
+                .style(if input.mute {
+                    button::primary
+                } else {
+                    button::secondary
+                })
+                .on_press(Message::ToggleInputMute),
+            );
+            view = view.push(slider(0..=100, level, Message::SetInputLevel).width(80.));
+        }
+        view.into()
+    }
+
+    fn sound_slider(&'_ self) -> Element<'_, Message> {
+        let slider = match self.bar_index {
+            SliderIndex::Channel(index) => self.channel_bar(index),
+            SliderIndex::Balance => self.balance_bar(),
+        };
         row![
-            button("<").on_press(Message::SliderIndexPre),
-            text(&self.right_text),
-            slider(0..=100, self.right as u8, Message::UpdateRight),
-            button(">").on_press(Message::SliderIndexNext)
+            button(text(if self.muted {
+                fl!("unmute")
+            } else {
+                fl!("mute")
+            }))
+            .style(if self.muted {
+                button::primary
+            } else {
+                button::secondary
+            })
+            .on_press(Message::ToggleMute),
+            slider,
+            button(text(fl!("mixer")))
+                .style(if self.mixer_popup_id.is_some() {
+                    button::primary
+                } else {
+                    button::secondary
+                })
+                .on_press(Message::ToggleMixerPopup),
+            button(text(fl!("devices")))
+                .style(if self.devices_popup_id.is_some() {
+                    button::primary
+                } else {
+                    button::secondary
+                })
+                .on_press(Message::ToggleDevicesPopup),
+            button(text("⇄")).style(button::secondary).on_press_maybe(
+                self.recent_outputs
+                    .previous(&self.devices)
+                    .map(|_| Message::SwitchRecentOutput)
+            ),
         ]
         .spacing(5.)
         .align_y(Alignment::Center)
         .into()
     }
-
-    fn sound_slider(&'_ self) -> Element<'_, Message> {
-        match self.bar_index {
-            SliderIndex::Left => self.left_bar(),
-            SliderIndex::Right => self.right_bar(),
-            SliderIndex::Balance => self.balance_bar(),
-        }
-    }
 }
 
 impl LalaMusicBar {
@@ -396,17 +1312,93 @@ impl LalaMusicBar {
             .height(Length::Fill)
             .into()
     }
+    /// The multi-line reply editor of the notification, if it can be replied to
+    fn reply_editor(&self, notify: &NotifyUnit) -> Option<Element<'_, Message>> {
+        use iced::keyboard::{Key, key::Named};
+        if !notify.inline_reply_support() {
+            return None;
+        }
+        let draft = self.reply_drafts.get(&notify.id)?;
+        let notify_id = notify.id;
+        Some(
+            row![
+                text_editor(draft)
+                    .id(inline_reply_id(notify_id))
+                    .placeholder(fl!("inline-reply-placeholder"))
+                    .max_height((MAX_REPLY_LINES as u32 * REPLY_LINE_HEIGHT) as f32)
+                    .on_action(move |action| Message::InlineReplyEdit((notify_id, action)))
+                    // NOTE: Enter sends the reply, Shift+Enter breaks the line
+                    .key_binding(move |key_press| match key_press.key {
+                        Key::Named(Named::Enter)
+                            if !key_press.modifiers.shift()
+                                && matches!(
+                                    key_press.status,
+                                    text_editor::Status::Focused { .. }
+                                ) =>
+                        {
+                            Some(text_editor::Binding::Custom(Message::InlineReply(
+                                notify_id,
+                            )))
+                        }
+                        _ => text_editor::Binding::from_key_press(key_press),
+                    }),
+                button(text(fl!("send"))).on_press(Message::InlineReply(notify_id)),
+            ]
+            .into(),
+        )
+    }
     fn right_notification(&'_ self) -> Element<'_, Message> {
+        // NOTE: the hidden notifications can be replied to here, with the draft of their popup
         let btns: Vec<Element<Message>> = self
             .hidden_notification()
             .iter()
             .map(|wdgetinfo| {
-                container(wdgetinfo.notify_button(self))
-                    .height(Length::Fixed(100.))
-                    .into()
+                let entry = container(wdgetinfo.notify_button(self)).height(Length::Fixed(100.));
+                match self.reply_editor(&wdgetinfo.unit) {
+                    Some(editor) => column![entry, editor].spacing(5.).into(),
+                    None => entry.into(),
+                }
             })
             .collect();
+        let mut history = column(btns).spacing(10.);
+        if !self.reply_threads.is_empty() {
+            let threads = self.reply_threads.iter().map(|thread| {
+                let mut replies: Vec<Element<Message>> = vec![
+                    text(format!("{} - {}", thread.app_name, thread.summary))
+                        .font(Font {
+                            weight: iced::font::Weight::Bold,
+                            ..Default::default()
+                        })
+                        .shaping(text::Shaping::Advanced)
+                        .into(),
+                ];
+                replies.extend(thread.replies.iter().map(|(time, reply)| {
+                    row![
+                        text(time.format("%H:%M").to_string()).size(12),
+                        Space::new().width(5.),
+                        text(reply).shaping(text::Shaping::Advanced)
+                    ]
+                    .into()
+                }));
+                container(column(replies).spacing(4.))
+                    .padding(5.)
+                    .width(Length::Fill)
+                    .style(container::rounded_box)
+                    .into()
+            });
+            history = history
+                .push(text(fl!("sent-replies")).size(18))
+                .extend(threads);
+        }
         let mut view_elements: Vec<Element<Message>> = vec![];
+        if let Some(data) = &self.service_data {
+            view_elements.push(
+                container(self.player_switcher(data))
+                    .padding(10)
+                    .width(Length::Fill)
+                    .into(),
+            );
+        }
         if let (Some(data), Some(handle)) = (
             &self.service_data,
             self.service_data
@@ -437,13 +1429,25 @@ impl LalaMusicBar {
                 .center_x(Length::Fill)
                 .into(),
             );
+            view_elements.push(
+                container(self.seek_bar(data))
+                    .padding([0, 10])
+                    .width(Length::Fill)
+                    .into(),
+            );
+            view_elements.push(
+                container(self.media_controls(data))
+                    .padding([0, 10])
+                    .width(Length::Fill)
+                    .into(),
+            );
             view_elements.push(Space::new().height(10.).into());
         }
         view_elements.append(&mut vec![
             Space::new().height(10.).into(),
             scrollable(row!(
                 Space::new().width(10.),
-                column(btns).spacing(10.),
+                history,
                 Space::new().width(10.)
             ))
             .height(Length::Fill)
@@ -468,6 +1472,366 @@ impl LalaMusicBar {
 }
 
 impl LalaMusicBar {
+    /// The most played artists and tracks of the week, and the button to export the history
+    fn history_view<'a>(&'a self, stats: &'a HistoryStats) -> Element<'a, Message> {
+        let mut view = column![text(fl!("history-week")).size(14)].spacing(5.);
+        if stats.top_artists.is_empty() {
+            view = view.push(text(fl!("history-empty")).size(12));
+        }
+        for (artist, count) in &stats.top_artists {
+            view = view.push(
+                row![
+                    text(artist).shaping(text::Shaping::Advanced),
+                    Space::new().width(Length::Fill),
+                    text(count.to_string()).size(12),
+                ]
+                .align_y(Alignment::Center),
+            );
+        }
+        if !stats.top_tracks.is_empty() {
+            view = view.push(Space::new().height(Length::Fixed(5.)));
+        }
+        for ((title, artists), count) in &stats.top_tracks {
+            view = view.push(
+                row![
+                    column![
+                        text(title).shaping(text::Shaping::Advanced),
+                        text(artists).size(12).shaping(text::Shaping::Advanced),
+                    ]
+                    .width(Length::Fill),
+                    text(count.to_string()).size(12),
+                ]
+                .align_y(Alignment::Center),
+            );
+        }
+        view = view.push(
+            row![
+                button(text(fl!("history-export")))
+                    .style(button::secondary)
+                    .on_press(Message::ExportHistory),
+                text(self.history_export.as_deref().unwrap_or_default()).size(12),
+            ]
+            .spacing(5.)
+            .align_y(Alignment::Center),
+        );
+        view.into()
+    }
+
+    fn devices_view(&self) -> Element<'_, Message> {
+        let devices = &self.devices;
+        if devices.outputs.is_empty() && devices.inputs.is_empty() && devices.cards.is_empty() {
+            return container(text(fl!("no-devices")))
+                .center_x(Length::Fill)
+                .center_y(Length::Fill)
+                .into();
+        }
+        let choice = |label: &str, active: bool, message: Option<Message>| {
+            button(text(label.to_owned()).shaping(text::Shaping::Advanced))
+                .width(Length::Fill)
+                .style(if active {
+                    button::primary
+                } else {
+                    button::secondary
+                })
+                .on_press_maybe(message)
+        };
+        let device_list = |title: String, kind: DeviceKind, list: &[Device]| {
+            column![text(title).size(16)]
+                .extend(list.iter().map(|device| {
+                    choice(
+                        &device.description,
+                        devices.is_default(kind, &device.name),
+                        Some(Message::SetDefaultDevice((kind, device.name.clone()))),
+                    )
+                    .into()
+                }))
+                .spacing(5.)
+        };
+        let mut view = column![
+            device_list(fl!("outputs"), DeviceKind::Output, &devices.outputs),
+            device_list(fl!("inputs"), DeviceKind::Input, &devices.inputs),
+        ]
+        .spacing(15.)
+        .padding(10.);
+        if !devices.cards.is_empty() {
+            let cards = devices.cards.iter().map(|card| {
+                column![text(&card.description).shaping(text::Shaping::Advanced)]
+                    .extend(card.profiles.iter().map(|profile| {
+                        choice(
+                            &profile.description,
+                            card.active == Some(profile.index),
+                            profile
+                                .available
+                                .then_some(Message::SetProfile((card.id, profile.index))),
+                        )
+                        .into()
+                    }))
+                    .spacing(5.)
+                    .into()
+            });
+            view = view.push(
+                column![text(fl!("profiles")).size(16)]
+                    .extend(cards)
+                    .spacing(10.),
+            );
+        }
+        container(scrollable(view)).into()
+    }
+
+    fn mixer_view(&self) -> Element<'_, Message> {
+        if self.streams.is_empty() {
+            return container(text(fl!("no-streams")))
+                .center_x(Length::Fill)
+                .center_y(Length::Fill)
+                .into();
+        }
+        let streams = self.streams.iter().map(|stream| {
+            let icon: Element<'_, Message> = match &stream.icon {
+                Some(path) if path.extension().is_some_and(|extension| extension == "png") => {
+                    image(image::Handle::from_path(path))
+                        .width(Length::Fixed(30.))
+                        .height(Length::Fixed(30.))
+                        .into()
+                }
+                Some(path) => svg(svg::Handle::from_path(path))
+                    .width(Length::Fixed(30.))
+                    .height(Length::Fixed(30.))
+                    .into(),
+                None => Space::new().width(Length::Fixed(30.)).into(),
+            };
+            let level = stream
+                .volume
+                .channels
+                .iter()
+                .copied()
+                .max()
+                .unwrap_or(0)
+                .clamp(0, 100) as u8;
+            let id = stream.id;
+            column![
+                row![
+                    icon,
+                    column![
+                        text(&stream.name).shaping(text::Shaping::Advanced),
+                        text(&stream.media)
+                            .size(12)
+                            .shaping(text::Shaping::Advanced),
+                    ]
+                    .width(Length::Fill),
+                    button(text(if stream.volume.mute {
+                        fl!("unmute")
+                    } else {
+                        fl!("mute")
+                    }))
+                    .style(if stream.volume.mute {
+                        button::primary
+                    } else {
+                        button::secondary
+                    })
+                    .on_press(Message::ToggleStreamMute(id)),
+                ]
+                .spacing(10.)
+                .align_y(Alignment::Center),
+                slider(0..=100, level, move |percent| {
+                    Message::SetStreamVolume((id, percent))
+                }),
+            ]
+            .spacing(5.)
+            .into()
+        });
+        container(scrollable(
+            column![text(fl!("mixer")).size(16)]
+                .extend(streams)
+                .spacing(15.)
+                .padding(10.),
+        ))
+        .into()
+    }
+
+    fn media_view(&self) -> Element<'_, Message> {
+        let Some(data) = &self.service_data else {
+            return container(text(fl!("no-player")))
+                .center_x(Length::Fill)
+                .center_y(Length::Fill)
+                .into();
+        };
+        let mut header = row![
+            text(&data.identity)
+                .size(16)
+                .font(Font {
+                    weight: iced::font::Weight::Bold,
+                    ..Default::default()
+                })
+                .shaping(text::Shaping::Advanced),
+            Space::new().width(Length::Fill),
+        ]
+        .spacing(5.)
+        .align_y(Alignment::Center);
+        if data.can_raise {
+            header = header.push(
+                button(text(fl!("raise-player")))
+                    .style(button::secondary)
+                    .on_press(Message::RaisePlayer),
+            );
+        }
+        if data.can_quit {
+            header = header.push(
+                button(text(fl!("quit-player")))
+                    .style(button::danger)
+                    .on_press(Message::QuitPlayer),
+            );
+        }
+        let mut view = column![header].spacing(10.);
+        if data.state == ConnectionState::Degraded {
+            view = view.push(text(fl!("player-degraded")).size(12).style(text::warning));
+        }
+        if let Some(handle) = &data.metadata.mpris_image {
+            view = view
+                .push(container(image(handle).height(Length::Fixed(300.))).center_x(Length::Fill));
+        }
+        view = view.push(
+            column![
+                text(&data.metadata.xesam_title)
+                    .size(22)
+                    .font(Font {
+                        weight: iced::font::Weight::Bold,
+                        ..Default::default()
+                    })
+                    .shaping(text::Shaping::Advanced),
+                text(data.metadata.artists().join(", ")).shaping(text::Shaping::Advanced),
+                text(data.metadata.album())
+                    .size(14)
+                    .shaping(text::Shaping::Advanced),
+            ]
+            .spacing(2.),
+        );
+        if self.bar_settings.lyrics.popup
+            && let Some((line, next)) = self.lyric_lines(data)
+        {
+            view = view.push(
+                column![
+                    text(line)
+                        .size(16)
+                        .font(Font {
+                            weight: iced::font::Weight::Bold,
+                            ..Default::default()
+                        })
+                        .shaping(text::Shaping::Advanced),
+                    text(next.unwrap_or_default())
+                        .size(12)
+                        .shaping(text::Shaping::Advanced)
+                        .style(text::secondary),
+                ]
+                .spacing(2.),
+            );
+        }
+        view = view
+            .push(self.seek_bar(data))
+            .push(container(self.transport_buttons(data)).center_x(Length::Fill))
+            .push(self.media_controls(data));
+        // NOTE: if the current track is not in the list, all the tracks are up next
+        let current = data
+            .tracks
+            .iter()
+            .position(|track| track.track_id() == data.metadata.track_id());
+        let up_next: Vec<Element<Message>> = data
+            .tracks
+            .iter()
+            .skip(current.map_or(0, |index| index + 1))
+            .map(|track| {
+                button(
+                    column![
+                        text(&track.xesam_title).shaping(text::Shaping::Advanced),
+                        text(track.artists().join(", "))
+                            .size(12)
+                            .shaping(text::Shaping::Advanced),
+                    ]
+                    .spacing(2.),
+                )
+                .style(button::text)
+                .width(Length::Fill)
+                .on_press(Message::GoToTrack(track.track_id().to_owned()))
+                .into()
+            })
+            .collect();
+        if !up_next.is_empty() {
+            view = view
+                .push(text(fl!("up-next")).size(14))
+                .push(column(up_next).spacing(2.));
+        }
+        if let Some(playlists) = data.playlists.as_ref().filter(|list| !list.is_empty()) {
+            let playlists = playlists.iter().map(|playlist| {
+                let active = data.active_playlist.as_deref() == Some(playlist.id());
+                button(text(&playlist.name).shaping(text::Shaping::Advanced))
+                    .style(if active {
+                        button::primary
+                    } else {
+                        button::secondary
+                    })
+                    .width(Length::Fill)
+                    .on_press(Message::ActivatePlaylist(playlist.id().to_owned()))
+                    .into()
+            });
+            view = view
+                .push(text(fl!("playlists")).size(14))
+                .push(column(playlists).spacing(5.));
+        }
+        if let Some(stats) = self
+            .history_stats
+            .as_ref()
+            .filter(|_| self.bar_settings.history.enabled)
+        {
+            view = view.push(self.history_view(stats));
+        }
+        let others: Vec<Element<Message>> = self
+            .players
+            .iter()
+            .filter(|info| info.service_path() != data.service_path())
+            .map(|info| {
+                button(
+                    column![
+                        text(&info.identity).size(12),
+                        text(&info.metadata.xesam_title).shaping(text::Shaping::Advanced),
+                    ]
+                    .spacing(2.),
+                )
+                .style(button::secondary)
+                .width(Length::Fill)
+                .on_press(Message::SelectPlayer(info.service_path().to_owned()))
+                .into()
+            })
+            .collect();
+        if !others.is_empty() {
+            view = view
+                .push(text(fl!("other-players")).size(14))
+                .push(column(others).spacing(5.));
+        }
+        if !self.unavailable_players.is_empty() {
+            let unavailable = self.unavailable_players.iter().map(|(name, state)| {
+                let state = match state {
+                    ConnectionState::Retrying { attempt } => {
+                        let mut args = FluentArgs::new();
+                        args.set("attempt", attempt);
+                        fl!("player-retrying", args)
+                    }
+                    _ => fl!("player-gone"),
+                };
+                row![
+                    text(player_name(name)).shaping(text::Shaping::Advanced),
+                    Space::new().width(Length::Fill),
+                    text(state).size(12).style(text::secondary),
+                ]
+                .into()
+            });
+            view = view
+                .push(text(fl!("unavailable-players")).size(14))
+                .push(column(unavailable).spacing(5.));
+        }
+        container(scrollable(view.padding(10.)).height(Length::Fill))
+            .style(container::rounded_box)
+            .into()
+    }
+
     fn main_view(&self) -> Element<'_, Message> {
         let toggle_launcher = button(
             svg(svg::Handle::from_memory(LAUNCHER_SVG))
@@ -477,6 +1841,7 @@ impl LalaMusicBar {
         .on_press(Message::ToggleLauncher);
 
         let sound_slider = container(self.sound_slider()).center_y(Length::Fill);
+        let microphone = container(self.microphone_widget()).center_y(Length::Fill);
         let panel_text = if self.right_panel.is_some() { ">" } else { "<" };
 
         let panel_btn = container(button(text(panel_text)).on_press(Message::ToggleRightPanel))
@@ -486,6 +1851,7 @@ impl LalaMusicBar {
                 toggle_launcher,
                 Space::new().width(Length::Fill),
                 container(sound_slider).width(600.),
+                microphone,
                 Space::new().width(Length::Fixed(3.)),
                 self.date_widget(),
                 Space::new().width(Length::Fixed(3.)),
@@ -499,23 +1865,39 @@ impl LalaMusicBar {
                 .center_y(Length::Fill)
                 .into();
         };
-        let title = &service_data.metadata.xesam_title;
+        let mut title = service_data
+            .metadata
+            .format(self.bar_settings.media.title_format());
+        if self.bar_settings.lyrics.bar
+            && let Some((line, _)) = self.lyric_lines(service_data)
+            && !line.is_empty()
+        {
+            title = line.to_owned();
+        }
         let handle_option = &service_data.metadata.mpris_image;
 
-        let title = container(
-            text(title)
-                .size(20)
-                .font(Font {
-                    weight: iced::font::Weight::Bold,
-                    ..Default::default()
-                })
-                .shaping(text::Shaping::Advanced)
-                .style(|_theme| text::Style {
-                    color: Some(iced::Color::WHITE),
-                }),
+        let title = button(
+            container(
+                text(title)
+                    .size(20)
+                    .font(Font {
+                        weight: iced::font::Weight::Bold,
+                        ..Default::default()
+                    })
+                    .shaping(text::Shaping::Advanced)
+                    .style(|_theme| text::Style {
+                        color: Some(iced::Color::WHITE),
+                    }),
+            )
+            .center_x(Length::Fill),
         )
+        .style(button::text)
+        .padding(0)
         .width(Length::Fill)
-        .center_x(Length::Fill);
+        .on_press(Message::ToggleMediaPopup);
+        let seek_bar = container(self.seek_bar(service_data))
+            .width(Length::Fixed(250.))
+            .center_y(Length::Fill);
         let wav_chat: Element<'_, Message> = if self.bar_settings.spectrum_enable() {
             // NOTE: since drawing the canvas cost lots of cpu, so now we allow you to disable it
             canvas(&self.wav_data)
@@ -526,40 +1908,7 @@ impl LalaMusicBar {
             Space::new().into()
         };
 
-        let can_play = service_data.can_play;
-        let can_pause = service_data.can_pause;
-        let can_go_next = service_data.can_go_next;
-        let can_go_pre = service_data.can_go_previous;
-        let mut button_pre = button(svg(GO_PREVIOUS_HANDLE.clone()).width(25.).height(25.))
-            .width(30.)
-            .height(30.);
-        if can_go_pre {
-            button_pre = button_pre.on_press(Message::RequestPre);
-        }
-        let mut button_next = button(svg(GO_NEXT_HANDLE.clone()).width(25.).height(25.))
-            .width(30.)
-            .height(30.);
-        if can_go_next {
-            button_next = button_next.on_press(Message::RequestNext);
-        }
-        let button_play = if service_data.playback_status == "Playing" {
-            let mut btn = button(svg(PAUSE_HANDLE.clone()).width(25.).height(25.))
-                .width(30.)
-                .height(30.);
-            if can_pause {
-                btn = btn.on_press(Message::RequestPause);
-            }
-            btn
-        } else {
-            let mut btn = button(svg(PLAY_HANDLE.clone()).width(25.).height(25.))
-                .width(30.)
-                .height(30.);
-            if can_play {
-                btn = btn.on_press(Message::RequestPlay);
-            }
-            btn
-        };
-        let buttons = container(row![button_pre, button_play, button_next].spacing(5))
+        let buttons = container(self.transport_buttons(service_data))
             .width(Length::Fill)
             .center_x(Length::Fill)
             .center_y(Length::Fill);
@@ -568,11 +1917,16 @@ impl LalaMusicBar {
             row![
                 toggle_launcher,
                 Space::new().width(Length::Fixed(5.)),
-                image(handle),
+                button(image(handle))
+                    .style(button::text)
+                    .padding(0)
+                    .on_press(Message::ToggleMediaPopup),
                 title,
+                seek_bar,
                 wav_chat,
                 buttons,
                 sound_slider,
+                microphone,
                 Space::new().width(Length::Fixed(3.)),
                 self.date_widget(),
                 Space::new().width(Length::Fixed(3.)),
@@ -583,9 +1937,11 @@ impl LalaMusicBar {
             row![
                 toggle_launcher,
                 title,
+                seek_bar,
                 wav_chat,
                 buttons,
                 sound_slider,
+                microphone,
                 Space::new().width(Length::Fixed(3.)),
                 self.date_widget(),
                 Space::new().width(Length::Fixed(1.)),
@@ -605,14 +1961,29 @@ impl LalaMusicBar {
 
 impl LalaMusicBar {
     fn new() -> (Self, Command<Message>) {
+        let bar_settings = SettingsConfig::read_from_file();
+        audio::init(bar_settings.alsa.clone());
+        let selection = PlayerSelection {
+            policy: bar_settings.media.clone(),
+            ..Default::default()
+        };
         (
             Self {
                 service_data: None,
+                players: Vec::new(),
+                unavailable_players: Vec::new(),
+                selected_player: None,
                 left: 0,
                 right: 0,
-                left_text: "".to_string(),
-                right_text: "".to_string(),
+                channel_texts: Vec::new(),
                 balance_text: "".to_string(),
+                muted: false,
+                streams: Vec::new(),
+                output_volume: None,
+                input: None,
+                captures: Vec::new(),
+                devices: Devices::default(),
+                recent_outputs: RecentOutputs::default(),
                 bar_index: SliderIndex::Balance,
                 launcher: None,
                 launcherid: None,
@@ -623,21 +1994,48 @@ impl LalaMusicBar {
                 showned_notifications: HashMap::new(),
                 cached_notifications: HashMap::new(),
                 cached_hidden_notifications: Vec::new(),
+                focused_notification: None,
+                reply_drafts: HashMap::new(),
+                grown_popups: HashSet::new(),
+                reply_threads: Vec::new(),
+                banner_notifications: HashMap::new(),
+                banner_windows: HashMap::new(),
                 sender: None,
+                backend_sender: None,
+                now_playing: NowPlaying::default(),
                 check_sender: None,
                 quite_mode: false,
                 datetime: Local::now(),
                 calendar_id: None,
+                details_id: None,
+                details_notification: None,
+                link_confirm_id: None,
+                media_popup_id: None,
+                notify_timeouts: HashMap::new(),
+                notify_serial: 0,
+                mixer_popup_id: None,
+                devices_popup_id: None,
+                osd_id: None,
+                osd: None,
+                osd_serial: 0,
+                seek_preview: None,
+                player_volume_preview: None,
+                lyrics: None,
+                media_announcement: None,
+                listen_tracker: ListenTracker::default(),
+                history_stats: None,
+                history_export: None,
+                pending_link: None,
                 date: Date::today(),
                 time: Time::now_hm(true),
                 time_picker_id: None,
                 right_filter: RightPanelFilter::Notifications,
-                bar_settings: SettingsConfig::read_from_file(),
+                bar_settings,
                 wav_data: WavState::new(),
             },
             Command::batch(vec![
                 Command::done(Message::UpdateData),
-                Command::perform(get_metadata_initial(), Message::MpirsInfoUpdate),
+                Command::perform(get_metadata_initial(selection), Message::MpirsInfoUpdate),
             ]),
         )
     }
@@ -657,7 +2055,26 @@ impl LalaMusicBar {
             Some(LaLaInfo::RightPanel)
         } else if self.calendar_id.is_some_and(|tid| tid == id) {
             Some(LaLaInfo::Calendar)
+        } else if self.details_id.is_some_and(|tid| tid == id) {
+            Some(LaLaInfo::NotifyDetails)
+        } else if self.link_confirm_id.is_some_and(|tid| tid == id) {
+            Some(LaLaInfo::LinkConfirm)
+        } else if self.media_popup_id.is_some_and(|tid| tid == id) {
+            Some(LaLaInfo::MediaPopup)
+        } else if self.mixer_popup_id.is_some_and(|tid| tid == id) {
+            Some(LaLaInfo::MixerPopup)
+        } else if self.devices_popup_id.is_some_and(|tid| tid == id) {
+            Some(LaLaInfo::DevicesPopup)
+        } else if self.osd_id.is_some_and(|tid| tid == id) {
+            Some(LaLaInfo::Osd)
         } else {
+            if let Some((nid, _)) = self.banner_windows.get(&id) {
+                return self
+                    .banner_notifications
+                    .get(nid)
+                    .cloned()
+                    .map(|info| LaLaInfo::Notify(Box::new(info)));
+            }
             if let Some(info) = self.cached_notifications.get(&id) {
                 return Some(LaLaInfo::Notify(Box::new(info.clone())));
             }
@@ -685,6 +2102,12 @@ impl LalaMusicBar {
             }
             LaLaInfo::RightPanel => self.right_panel = Some(id),
             LaLaInfo::Calendar => self.calendar_id = Some(id),
+            LaLaInfo::NotifyDetails => self.details_id = Some(id),
+            LaLaInfo::LinkConfirm => self.link_confirm_id = Some(id),
+            LaLaInfo::MediaPopup => self.media_popup_id = Some(id),
+            LaLaInfo::MixerPopup => self.mixer_popup_id = Some(id),
+            LaLaInfo::DevicesPopup => self.devices_popup_id = Some(id),
+            LaLaInfo::Osd => self.osd_id = Some(id),
             LaLaInfo::TimePicker => self.time_picker_id = Some(id),
             _ => unreachable!(),
         }
@@ -707,6 +2130,37 @@ impl LalaMusicBar {
         if self.time_picker_id.is_some_and(|lid| lid == id) {
             self.time_picker_id.take();
         }
+        if self.details_id.is_some_and(|lid| lid == id) {
+            self.details_id.take();
+            self.details_notification.take();
+        }
+        if self.media_popup_id.is_some_and(|lid| lid == id) {
+            self.media_popup_id.take();
+        }
+        if self.mixer_popup_id.is_some_and(|lid| lid == id) {
+            self.mixer_popup_id.take();
+        }
+        if self.devices_popup_id.is_some_and(|lid| lid == id) {
+            self.devices_popup_id.take();
+        }
+        if self.osd_id.is_some_and(|lid| lid == id) {
+            self.osd_id.take();
+            self.osd.take();
+        }
+        if self.link_confirm_id.is_some_and(|lid| lid == id) {
+            self.link_confirm_id.take();
+            self.pending_link.take();
+        }
+        if self.focused_notification.is_some_and(|lid| lid == id) {
+            self.focused_notification.take();
+        }
+        self.grown_popups.remove(&id);
+        if let Some((nid, _)) = self.banner_windows.remove(&id)
+            && !self.banner_windows.values().any(|(onid, _)| *onid == nid)
+        {
+            self.banner_notifications.remove(&nid);
+            self.notifications_markdown.remove(&nid);
+        }
         'clear_nid: {
             if let Some(nid) = self.showned_notifications.remove(&id) {
                 if let Some(NotifyUnitWidgetInfo {
@@ -740,36 +2194,84 @@ impl LalaMusicBar {
             Message::Pw(PwEvent::PwErr) => {
                 tracing::warn!("pw connection is broken");
             }
-            Message::MpirsInfoUpdate(data) => self.service_data = data,
+            Message::MpirsInfoUpdate(state) => {
+                // NOTE: the selection is forgotten once the player is gone
+                if self.selected_player.as_ref().is_some_and(|selected| {
+                    !state
+                        .players
+                        .iter()
+                        .any(|info| info.service_path() == selected)
+                }) {
+                    self.selected_player.take();
+                }
+                self.service_data = state.current;
+                self.players = state.players;
+                self.unavailable_players = state.unavailable;
+                self.sync_now_playing();
+                let mut commands = vec![self.announce_track(), self.sync_lyrics()];
+                if self.bar_settings.history.enabled {
+                    let plays = self.listen_tracker.update(&self.players);
+                    if !plays.is_empty() && self.media_popup_id.is_some() {
+                        commands.push(Command::perform(
+                            async move {
+                                history::record(plays).await;
+                                history::weekly_stats().await
+                            },
+                            Message::HistoryStatsLoaded,
+                        ));
+                    } else if !plays.is_empty() {
+                        commands.push(Command::future(history::record(plays)).discard());
+                    }
+                }
+                return Command::batch(commands);
+            }
+            Message::HistoryStatsLoaded(stats) => {
+                self.history_stats = Some(stats);
+            }
+            Message::ExportHistory => {
+                return Command::perform(history::export(), Message::HistoryExported);
+            }
+            Message::HistoryExported(path) => {
+                self.history_export = Some(match path {
+                    Some(path) => path.display().to_string(),
+                    None => fl!("history-export-failed"),
+                });
+            }
+            Message::LyricsLoaded((key, lyrics)) => {
+                if let Some((loaded_key, loaded)) = &mut self.lyrics
+                    && *loaded_key == key
+                {
+                    *loaded = lyrics;
+                }
+            }
+            Message::SelectPlayer(service_path) => {
+                self.selected_player = Some(service_path);
+                return Command::perform(
+                    get_metadata(self.player_selection()),
+                    Message::MpirsInfoUpdate,
+                );
+            }
+            Message::PinPlayer(pin) => {
+                let Some(data) = &self.service_data else {
+                    return Command::none();
+                };
+                self.bar_settings.media.pinned =
+                    pin.then(|| player_name(data.service_path()).to_owned());
+                self.bar_settings.write_to_file();
+                self.selected_player.take();
+            }
             Message::ToggleCalendar => {
                 if let Some(calendar_id) = self.calendar_id {
                     return iced_runtime::task::effect(Action::Window(WindowAction::Close(
                         calendar_id,
                     )));
-                } else {
-                    let id = iced::window::Id::unique();
-                    self.set_id_info(id, LaLaInfo::Calendar);
-                    if let Some(time_picker_id) = self.time_picker_id {
-                        return iced_runtime::task::Task::batch([
-                            iced_runtime::task::effect(Action::Window(WindowAction::Close(
-                                time_picker_id,
-                            ))),
-                            Command::done(Message::NewLayerShell {
-                                settings: NewLayerShellSettings {
-                                    size: Some((350, 350)),
-                                    exclusive_zone: None,
-                                    anchor: Anchor::Right | Anchor::Bottom,
-                                    layer: Layer::Top,
-                                    margin: Some((10, 10, 10, 10)),
-                                    keyboard_interactivity: KeyboardInteractivity::None,
-                                    output_option: OutputOption::LastOutput,
-                                    ..Default::default()
-                                },
-                                id,
-                            }),
-                        ]);
-                    }
-                    return Command::done(Message::NewLayerShell {
+                }
+                let close = self.close_corner_popups();
+                let id = iced::window::Id::unique();
+                self.set_id_info(id, LaLaInfo::Calendar);
+                return Command::batch([
+                    close,
+                    Command::done(Message::NewLayerShell {
                         settings: NewLayerShellSettings {
                             size: Some((350, 350)),
                             exclusive_zone: None,
@@ -781,38 +2283,21 @@ impl LalaMusicBar {
                             ..Default::default()
                         },
                         id,
-                    });
-                }
+                    }),
+                ]);
             }
             Message::ToggleTime => {
                 if let Some(time_picker_id) = self.time_picker_id {
                     return iced_runtime::task::effect(Action::Window(WindowAction::Close(
                         time_picker_id,
                     )));
-                } else {
-                    let id = iced::window::Id::unique();
-                    self.set_id_info(id, LaLaInfo::TimePicker);
-                    if let Some(calendar_id) = self.calendar_id {
-                        return iced_runtime::task::Task::batch([
-                            iced_runtime::task::effect(Action::Window(WindowAction::Close(
-                                calendar_id,
-                            ))),
-                            Command::done(Message::NewLayerShell {
-                                settings: NewLayerShellSettings {
-                                    size: Some((350, 350)),
-                                    exclusive_zone: None,
-                                    anchor: Anchor::Right | Anchor::Bottom,
-                                    layer: Layer::Top,
-                                    margin: Some((10, 10, 10, 10)),
-                                    keyboard_interactivity: KeyboardInteractivity::None,
-                                    output_option: OutputOption::LastOutput,
-                                    ..Default::default()
-                                },
-                                id,
-                            }),
-                        ]);
-                    }
-                    return Command::done(Message::NewLayerShell {
+                }
+                let close = self.close_corner_popups();
+                let id = iced::window::Id::unique();
+                self.set_id_info(id, LaLaInfo::TimePicker);
+                return Command::batch([
+                    close,
+                    Command::done(Message::NewLayerShell {
                         settings: NewLayerShellSettings {
                             size: Some((350, 350)),
                             exclusive_zone: None,
@@ -824,8 +2309,8 @@ impl LalaMusicBar {
                             ..Default::default()
                         },
                         id,
-                    });
-                }
+                    }),
+                ]);
             }
             // NOTE: it is meaningless to pick the date now
             Message::SubmitDate(_) | Message::CancelDate => {
@@ -844,10 +2329,11 @@ impl LalaMusicBar {
                         return Command::none();
                     }
                     let data = data.clone();
+                    let selection = self.player_selection();
                     return Command::perform(
                         async move {
                             data.play().await.ok();
-                            get_metadata().await
+                            get_metadata(selection).await
                         },
                         Message::MpirsInfoUpdate,
                     );
@@ -859,40 +2345,171 @@ impl LalaMusicBar {
                         return Command::none();
                     }
                     let data = data.clone();
+                    let selection = self.player_selection();
                     return Command::perform(
                         async move {
                             data.pause().await.ok();
-                            get_metadata().await
+                            get_metadata(selection).await
                         },
                         Message::MpirsInfoUpdate,
                     );
                 }
             }
+            Message::RequestPlayPause => {
+                let Some(data) = &self.service_data else {
+                    return Command::none();
+                };
+                if data.playback_status == "Playing" {
+                    return Command::done(Message::RequestPause);
+                }
+                return Command::done(Message::RequestPlay);
+            }
+            Message::SeekBy(offset) => {
+                if !self.service_data.as_ref().is_some_and(|data| data.can_seek) {
+                    return Command::none();
+                }
+                return self.request_player(move |data| async move { data.seek(offset).await });
+            }
+            Message::CyclePlayer => {
+                let current = self
+                    .service_data
+                    .as_ref()
+                    .and_then(|data| {
+                        self.players
+                            .iter()
+                            .position(|info| info.service_path() == data.service_path())
+                    })
+                    .map_or(0, |index| index + 1);
+                let Some(next) = self
+                    .players
+                    .get(current)
+                    .or(self.players.first())
+                    .map(|info| info.service_path().to_owned())
+                else {
+                    return Command::none();
+                };
+                return Command::done(Message::SelectPlayer(next));
+            }
             Message::RequestPre => {
                 if let Some(ref data) = self.service_data {
                     if !data.can_go_previous {
                         return Command::none();
                     }
                     let data = data.clone();
+                    let selection = self.player_selection();
                     return Command::perform(
                         async move {
                             data.go_previous().await.ok();
-                            get_metadata().await
+                            get_metadata(selection).await
                         },
                         Message::MpirsInfoUpdate,
                     );
                 }
             }
+            Message::SeekChanged(seconds) => self.seek_preview = Some(seconds),
+            Message::SeekReleased => {
+                let (Some(seconds), Some(data)) = (self.seek_preview.take(), &self.service_data)
+                else {
+                    return Command::none();
+                };
+                if !data.can_seek {
+                    return Command::none();
+                }
+                let data = data.clone();
+                let selection = self.player_selection();
+                return Command::perform(
+                    async move {
+                        data.set_position((seconds * 1_000_000.) as i64).await.ok();
+                        get_metadata(selection).await
+                    },
+                    Message::MpirsInfoUpdate,
+                );
+            }
+            Message::ToggleMediaPopup => {
+                if let Some(id) = self.media_popup_id {
+                    return iced_runtime::task::effect(Action::Window(WindowAction::Close(id)));
+                }
+                if self.service_data.is_none() {
+                    return Command::none();
+                }
+                let id = iced::window::Id::unique();
+                self.set_id_info(id, LaLaInfo::MediaPopup);
+                self.history_export.take();
+                let stats = if self.bar_settings.history.enabled {
+                    Command::perform(history::weekly_stats(), Message::HistoryStatsLoaded)
+                } else {
+                    Command::none()
+                };
+                return Command::batch([
+                    stats,
+                    Command::done(Message::NewLayerShell {
+                        settings: NewLayerShellSettings {
+                            size: Some((420, 640)),
+                            exclusive_zone: None,
+                            anchor: Anchor::Left | Anchor::Bottom,
+                            layer: Layer::Top,
+                            margin: Some((10, 10, 10, 10)),
+                            keyboard_interactivity: KeyboardInteractivity::None,
+                            output_option: OutputOption::LastOutput,
+                            ..Default::default()
+                        },
+                        id,
+                    }),
+                ]);
+            }
+            Message::RaisePlayer => {
+                return self.request_player(|data| async move { data.raise().await });
+            }
+            Message::QuitPlayer => {
+                return self.request_player(|data| async move { data.quit().await });
+            }
+            Message::GoToTrack(track_id) => {
+                return self.request_player(move |data| async move { data.go_to(&track_id).await });
+            }
+            Message::ActivatePlaylist(playlist_id) => {
+                return self.request_player(move |data| async move {
+                    data.activate_playlist(&playlist_id).await
+                });
+            }
+            Message::ToggleShuffle => {
+                let Some(shuffle) = self.service_data.as_ref().and_then(|data| data.shuffle) else {
+                    return Command::none();
+                };
+                return self
+                    .request_player(move |data| async move { data.set_shuffle(!shuffle).await });
+            }
+            Message::CycleLoopStatus => {
+                let Some(status) = self.service_data.as_ref().and_then(|data| data.loop_status)
+                else {
+                    return Command::none();
+                };
+                return self.request_player(move |data| async move {
+                    data.set_loop_status(status.next()).await
+                });
+            }
+            Message::SetRate(rate) => {
+                return self.request_player(move |data| async move { data.set_rate(rate).await });
+            }
+            Message::PlayerVolumeChanged(volume) => self.player_volume_preview = Some(volume),
+            Message::PlayerVolumeReleased => {
+                let Some(volume) = self.player_volume_preview.take() else {
+                    return Command::none();
+                };
+                return self.request_player(move |data| async move {
+                    data.set_volume(volume / 100.).await
+                });
+            }
             Message::RequestNext => {
                 if let Some(ref data) = self.service_data {
                     if !data.can_go_next {
                         return Command::none();
                     }
                     let data = data.clone();
+                    let selection = self.player_selection();
                     return Command::perform(
                         async move {
                             data.go_next().await.ok();
-                            get_metadata().await
+                            get_metadata(selection).await
                         },
                         Message::MpirsInfoUpdate,
                     );
@@ -903,29 +2520,141 @@ impl LalaMusicBar {
                 if current_balance == 0 {
                     return Command::none();
                 }
-                self.set_balance(balance)
+                return self.set_balance(balance);
             }
             Message::UpdateData => {
-                // BALANCE
+                self.update_clock();
                 self.update_balance();
-                // TIME
-                self.datetime = Local::now();
-                self.date = self.datetime.date_naive().into();
-                self.time = self.datetime.time().into();
+            }
+            Message::UpdateClock => self.update_clock(),
+            Message::Shutdown => {
+                // NOTE: nothing to save, so do not wait for the history file
+                let plays = if self.bar_settings.history.enabled {
+                    self.listen_tracker.flush()
+                } else {
+                    Vec::new()
+                };
+                if plays.is_empty() {
+                    return iced_runtime::exit();
+                }
+                return Command::future(history::record(plays)).then(|_| iced_runtime::exit());
             }
             Message::RequestMprisInfoUpdate => {
-                return Command::perform(get_metadata(), Message::MpirsInfoUpdate);
+                return Command::perform(
+                    get_metadata(self.player_selection()),
+                    Message::MpirsInfoUpdate,
+                );
+            }
+            Message::UpdateChannel((index, percent)) => {
+                let Some(mut channels) = self
+                    .output_volume
+                    .as_ref()
+                    .map(|volume| volume.channels.clone())
+                else {
+                    return Command::none();
+                };
+                if let Some(channel) = channels.get_mut(index) {
+                    *channel = percent as i64;
+                }
+                return self.set_output_channels(&channels);
+            }
+            Message::VolumeChanged => {
+                if self.update_balance() {
+                    return self.volume_osd();
+                }
             }
-            Message::UpdateLeft(percent) => {
-                aximer::set_left(percent as i64);
+            Message::ShowOsd(osd) => return self.show_osd(osd),
+            Message::HideOsd(serial) => {
+                // NOTE: forget the surface before it is closed, so an OSD shown meanwhile opens a
+                // new one
+                if serial == self.osd_serial
+                    && let Some(id) = self.osd_id.take()
+                {
+                    return iced_runtime::task::effect(Action::Window(WindowAction::Close(id)));
+                }
+            }
+            Message::ToggleMixerPopup => {
+                if let Some(id) = self.mixer_popup_id {
+                    return iced_runtime::task::effect(Action::Window(WindowAction::Close(id)));
+                }
                 self.update_balance();
+                let close = self.close_corner_popups();
+                let id = iced::window::Id::unique();
+                self.set_id_info(id, LaLaInfo::MixerPopup);
+                return Command::batch([
+                    close,
+                    Command::done(Message::NewLayerShell {
+                        settings: NewLayerShellSettings {
+                            size: Some((420, 360)),
+                            exclusive_zone: None,
+                            anchor: Anchor::Right | Anchor::Bottom,
+                            layer: Layer::Top,
+                            margin: Some((10, 10, 10, 10)),
+                            keyboard_interactivity: KeyboardInteractivity::None,
+                            output_option: OutputOption::LastOutput,
+                            ..Default::default()
+                        },
+                        id,
+                    }),
+                ]);
             }
-            Message::UpdateRight(percent) => {
-                aximer::set_right(percent as i64);
+            Message::ToggleDevicesPopup => {
+                if let Some(id) = self.devices_popup_id {
+                    return iced_runtime::task::effect(Action::Window(WindowAction::Close(id)));
+                }
                 self.update_balance();
+                let close = self.close_corner_popups();
+                let id = iced::window::Id::unique();
+                self.set_id_info(id, LaLaInfo::DevicesPopup);
+                return Command::batch([
+                    close,
+                    Command::done(Message::NewLayerShell {
+                        settings: NewLayerShellSettings {
+                            size: Some((420, 480)),
+                            exclusive_zone: None,
+                            anchor: Anchor::Right | Anchor::Bottom,
+                            layer: Layer::Top,
+                            margin: Some((10, 10, 10, 10)),
+                            keyboard_interactivity: KeyboardInteractivity::None,
+                            output_option: OutputOption::LastOutput,
+                            ..Default::default()
+                        },
+                        id,
+                    }),
+                ]);
+            }
+            Message::SetDefaultDevice((kind, name)) => {
+                audio::backend().set_default_device(kind, &name);
+            }
+            Message::SetProfile((card, index)) => audio::backend().set_profile(card, index),
+            Message::SwitchRecentOutput => {
+                if let Some(device) = self.recent_outputs.previous(&self.devices) {
+                    audio::backend().set_default_device(DeviceKind::Output, &device.name);
+                }
+            }
+            Message::SetInputLevel(percent) => audio::backend().set_input_level(percent as i64),
+            Message::ToggleInputMute => {
+                if let Some(input) = &self.input {
+                    audio::backend().set_input_mute(!input.mute);
+                }
+            }
+            Message::SetStreamVolume((id, percent)) => {
+                audio::backend().set_stream_volume(id, percent as i64);
+            }
+            Message::ToggleStreamMute(id) => {
+                if let Some(stream) = self.streams.iter().find(|stream| stream.id == id) {
+                    audio::backend().set_stream_mute(id, !stream.volume.mute);
+                }
+            }
+            Message::ToggleMute => {
+                return self.set_output_mute(!self.muted);
+            }
+            Message::SliderIndexNext => {
+                self.bar_index = self.bar_index.next(self.channel_texts.len())
+            }
+            Message::SliderIndexPre => {
+                self.bar_index = self.bar_index.pre(self.channel_texts.len())
             }
-            Message::SliderIndexNext => self.bar_index = self.bar_index.next(),
-            Message::SliderIndexPre => self.bar_index = self.bar_index.pre(),
             Message::ToggleLauncher => {
                 if self.launcher.is_some() {
                     if let Some(id) = self.launcherid {
@@ -1004,36 +2733,55 @@ impl LalaMusicBar {
             }
             Message::Notify(NotifyMessage::UnitAdd(notify)) => {
                 if let Some(onotify) = self.notifications.get_mut(&notify.id) {
-                    onotify.unit = *notify;
-                    return Command::none();
+                    self.notifications_markdown
+                        .insert(notify.id, markdown::parse(&notify.body).collect());
+                    onotify.unit = *notify.clone();
+                    return self.restart_notify_timeout(&notify);
                 }
-                let mut commands = vec![];
-                for (_, notify) in self.notifications.iter_mut() {
-                    notify.upper += 135;
-                    notify.counter += 1;
+                if let Some(onotify) = self.banner_notifications.get_mut(&notify.id) {
+                    self.notifications_markdown
+                        .insert(notify.id, markdown::parse(&notify.body).collect());
+                    onotify.unit = *notify.clone();
+                    return self.restart_notify_timeout(&notify);
                 }
-
+                let urgency = notify.urgency();
+                let policy = self.bar_settings.notification_policy(urgency).clone();
                 // NOTE: support timeout
-                if notify.timeout != -1 {
-                    let timeout = notify.timeout as u64;
-                    let id = notify.id;
-                    commands.push(Command::perform(
-                        async move {
-                            tokio::time::sleep(std::time::Duration::from_secs(timeout)).await
-                        },
-                        move |_| Message::RemoveNotify(id),
-                    ))
-                }
+                let mut commands = vec![self.restart_notify_timeout(&notify)];
 
                 self.notifications_markdown
                     .insert(notify.id, markdown::parse(&notify.body).collect());
+                if notify.inline_reply_support() {
+                    self.reply_drafts.entry(notify.id).or_default();
+                }
+
+                if !self
+                    .bar_settings
+                    .notification
+                    .banner_outputs(urgency)
+                    .is_empty()
+                    || (policy.bypass_hidden && self.quite_mode)
+                {
+                    commands.push(self.open_banner(NotifyUnitWidgetInfo {
+                        to_delete: false,
+                        counter: 0,
+                        upper: BEGINNING_UP_MARGIN,
+                        unit: *notify,
+                    }));
+                    return Command::batch(commands);
+                }
+
+                for (_, notify) in self.notifications.iter_mut() {
+                    notify.upper += 135;
+                    notify.counter += 1;
+                }
+
                 self.notifications.insert(
                     notify.id,
                     NotifyUnitWidgetInfo {
                         to_delete: false,
                         counter: 0,
                         upper: 10,
-                        inline_reply: String::new(),
                         unit: *notify.clone(),
                     },
                 );
@@ -1061,6 +2809,8 @@ impl LalaMusicBar {
                                 (**onid) = *nid;
                             }
                         }
+                        drop(showned_values);
+                        commands.push(self.sync_popup_layers());
                     } else {
                         // NOTE: remove the new one
                         let to_adjust_notification = &showned_notifications_now[1..];
@@ -1083,7 +2833,6 @@ impl LalaMusicBar {
                                 to_delete: false,
                                 counter: 0,
                                 upper: 10,
-                                inline_reply: String::new(),
                                 unit: *notify.clone(),
                             })),
                         );
@@ -1093,7 +2842,7 @@ impl LalaMusicBar {
                                 size: Some((300, 130)),
                                 exclusive_zone: None,
                                 anchor: Anchor::Right | Anchor::Top,
-                                layer: Layer::Top,
+                                layer: policy.layer(),
                                 margin: Some((10, 10, 10, 10)),
                                 keyboard_interactivity: KeyboardInteractivity::OnDemand,
                                 output_option: OutputOption::LastOutput,
@@ -1103,6 +2852,33 @@ impl LalaMusicBar {
                             id,
                         }));
                     }
+                    commands.push(self.sync_popup_sizes());
+                }
+
+                // NOTE: the ones pushed out of the stack become banners if they bypass the
+                // hidden list
+                let pushed_out: Vec<u32> = self
+                    .notifications
+                    .iter()
+                    .filter(|(_, info)| {
+                        info.counter >= MAX_SHOWN_NOTIFICATIONS_COUNT
+                            && !info.to_delete
+                            && self
+                                .bar_settings
+                                .notification_policy(info.unit.urgency())
+                                .bypass_hidden
+                    })
+                    .map(|(nid, _)| *nid)
+                    .collect();
+                for nid in pushed_out {
+                    let Some(info) = self.notifications.get(&nid).cloned() else {
+                        continue;
+                    };
+                    commands.push(self.remove_notify(nid));
+                    commands.push(self.open_banner(NotifyUnitWidgetInfo {
+                        to_delete: false,
+                        ..info
+                    }));
                 }
 
                 self.update_hidden_notification();
@@ -1158,7 +2934,10 @@ impl LalaMusicBar {
                                 size: Some((300, 130)),
                                 exclusive_zone: None,
                                 anchor: Anchor::Right | Anchor::Top,
-                                layer: Layer::Top,
+                                layer: self
+                                    .bar_settings
+                                    .notification_policy(notify_info.unit.urgency())
+                                    .layer(),
                                 margin: Some((notify_info.upper, 10, 10, 10)),
                                 keyboard_interactivity: KeyboardInteractivity::OnDemand,
                                 output_option: OutputOption::LastOutput,
@@ -1207,7 +2986,17 @@ impl LalaMusicBar {
                     return launcher.update(message, id);
                 }
             }
-            Message::InlineReply((notify_id, text)) => {
+            Message::InlineReply(notify_id) => {
+                let text = self
+                    .reply_drafts
+                    .get(&notify_id)
+                    .map(|draft| draft.text().trim_end().to_owned())
+                    .unwrap_or_default();
+                if text.is_empty() {
+                    return Command::none();
+                }
+                self.reply_drafts.remove(&notify_id);
+                self.record_reply(notify_id, text.clone());
                 self.sender
                     .as_mut()
                     .unwrap()
@@ -1219,22 +3008,169 @@ impl LalaMusicBar {
                 return self.remove_notify(notify_id);
             }
             Message::RemoveNotify(notify_id) => {
+                if notify_id != MEDIA_NOTIFICATION_ID {
+                    self.sender
+                        .as_mut()
+                        .unwrap()
+                        .try_send(NotifyCommand::ActionInvoked {
+                            id: notify_id,
+                            action_key: DEFAULT_ACTION.to_string(),
+                        })
+                        .ok();
+                }
+                return self.remove_notify(notify_id);
+            }
+            Message::NotifyTimeout((notify_id, serial)) => {
+                // NOTE: a notification with a reply being written stays until it is sent
+                let drafting = self
+                    .reply_drafts
+                    .get(&notify_id)
+                    .is_some_and(|draft| !draft.text().trim().is_empty());
+                if self.notify_timeouts.get(&notify_id) == Some(&serial) && !drafting {
+                    return Command::done(Message::RemoveNotify(notify_id));
+                }
+            }
+            Message::DismissNotify(notify_id) => {
+                if notify_id != MEDIA_NOTIFICATION_ID {
+                    self.sender
+                        .as_mut()
+                        .unwrap()
+                        .try_send(NotifyCommand::NotificationClosed {
+                            id: notify_id,
+                            reason: NOTIFICATION_DELETED_BY_USER,
+                        })
+                        .ok();
+                }
+                return self.remove_notify(notify_id);
+            }
+            // NOTE: the actions of the track notification are handled by the bar itself
+            Message::InvokeAction((MEDIA_NOTIFICATION_ID, action_key)) => {
+                let request = match action_key.as_str() {
+                    "pause" => Command::done(Message::RequestPause),
+                    "next" => Command::done(Message::RequestNext),
+                    _ => Command::none(),
+                };
+                return Command::batch([request, self.remove_notify(MEDIA_NOTIFICATION_ID)]);
+            }
+            Message::InvokeAction((notify_id, action_key)) => {
                 self.sender
                     .as_mut()
                     .unwrap()
                     .try_send(NotifyCommand::ActionInvoked {
                         id: notify_id,
-                        action_key: DEFAULT_ACTION.to_string(),
+                        action_key,
                     })
                     .ok();
                 return self.remove_notify(notify_id);
             }
-            Message::InlineReplyMsgUpdate((id, msg)) => {
-                let Some(notify_id) = self.showned_notifications.get(&id) else {
+            Message::DismissNewestNotify => {
+                if let Some(notify_id) = self.newest_notification() {
+                    return Command::done(Message::DismissNotify(notify_id));
+                }
+            }
+            Message::DismissAllNotify => {
+                for notify_id in self
+                    .notifications
+                    .keys()
+                    .chain(self.banner_notifications.keys())
+                    .filter(|id| **id != MEDIA_NOTIFICATION_ID)
+                {
+                    self.sender
+                        .as_mut()
+                        .unwrap()
+                        .try_send(NotifyCommand::NotificationClosed {
+                            id: *notify_id,
+                            reason: NOTIFICATION_DELETED_BY_USER,
+                        })
+                        .ok();
+                }
+                // NOTE: the popups are closed at once, the focus should not point to them
+                self.focused_notification.take();
+                return Command::done(Message::ClearAllNotifications);
+            }
+            Message::InvokeNewestNotify => {
+                if let Some(notify_id) = self.newest_notification() {
+                    return Command::done(Message::RemoveNotify(notify_id));
+                }
+            }
+            Message::ReplyNewestNotify => {
+                let Some(notify_id) = self.newest_notification() else {
+                    return Command::none();
+                };
+                if !self
+                    .notifications
+                    .get(&notify_id)
+                    .is_some_and(|info| info.unit.inline_reply_support())
+                {
+                    return Command::none();
+                }
+                let Some((id, _)) = self
+                    .visible_notifications()
+                    .into_iter()
+                    .find(|(_, nid)| *nid == notify_id)
+                else {
+                    return Command::none();
+                };
+                return Command::batch([
+                    self.focus_notification(Some(id)),
+                    focus(inline_reply_id(notify_id)),
+                ]);
+            }
+            Message::FocusNextNotify => {
+                let visible = self.visible_notifications();
+                let next = match self
+                    .focused_notification
+                    .and_then(|fid| visible.iter().position(|(id, _)| *id == fid))
+                {
+                    Some(index) => visible.get(index + 1),
+                    None => visible.first(),
+                };
+                return self.focus_notification(next.map(|(id, _)| *id));
+            }
+            Message::NotifyKeyPressed((id, key)) => {
+                use iced::keyboard::{Key, key::Named};
+                if self.cached_notifications.contains_key(&id) {
+                    return Command::none();
+                }
+                let Some(notify_id) = self
+                    .showned_notifications
+                    .get(&id)
+                    .copied()
+                    .or_else(|| self.banner_windows.get(&id).map(|(nid, _)| *nid))
+                else {
                     return Command::none();
                 };
-                let notify = self.notifications.get_mut(notify_id).unwrap();
-                notify.inline_reply = msg;
+                match key {
+                    Key::Named(Named::Escape) => {
+                        return Command::done(Message::DismissNotify(notify_id));
+                    }
+                    Key::Named(Named::Enter) => {
+                        return Command::done(Message::RemoveNotify(notify_id));
+                    }
+                    Key::Character(c) => {
+                        // NOTE: number keys start from 1
+                        let Some(index) = c.parse::<usize>().ok().filter(|index| *index > 0) else {
+                            return Command::none();
+                        };
+                        let Some((action_key, _)) = self
+                            .notification_info(notify_id)
+                            .and_then(|info| info.unit.action_pairs().into_iter().nth(index - 1))
+                        else {
+                            return Command::none();
+                        };
+                        return Command::done(Message::InvokeAction((notify_id, action_key)));
+                    }
+                    _ => {}
+                }
+            }
+            Message::InlineReplyEdit((notify_id, action)) => {
+                let extra_height = self.reply_extra_height(notify_id);
+                if let Some(draft) = self.reply_drafts.get_mut(&notify_id) {
+                    draft.perform(action);
+                }
+                if self.reply_extra_height(notify_id) != extra_height {
+                    return self.sync_popup_sizes();
+                }
             }
             Message::ClearAllNotifications => {
                 let mut commands = self
@@ -1255,8 +3191,16 @@ impl LalaMusicBar {
                     }
                 }
 
+                let banners: Vec<u32> = self.banner_notifications.keys().copied().collect();
+                for nid in banners {
+                    commands.push(self.remove_banner(nid));
+                }
+
                 self.notifications_markdown.clear();
                 self.notifications.clear();
+                self.reply_drafts.clear();
+                self.grown_popups.clear();
+                self.notify_timeouts.clear();
                 self.update_hidden_notification();
                 commands.push(Command::done(Message::CheckOutput));
                 return Command::batch(commands);
@@ -1265,13 +3209,99 @@ impl LalaMusicBar {
                 return iced_runtime::task::effect(Action::Window(WindowAction::Close(id)));
             }
             Message::Ready(sender) => self.sender = Some(sender),
+            Message::BackendReady(sender) => {
+                self.backend_sender = Some(sender);
+                self.now_playing = NowPlaying::default();
+                self.sync_now_playing();
+            }
             Message::ReadyCheck(check_sender) => self.check_sender = Some(check_sender),
             Message::CheckId(id) => {
-                let contain = self.notifications.contains_key(&id);
+                // NOTE: the id of the track notification cannot be replaced by other senders, they
+                // get a new id instead
+                let contain = id != MEDIA_NOTIFICATION_ID
+                    && (self.notifications.contains_key(&id)
+                        || self.banner_notifications.contains_key(&id));
                 let _ = self.check_sender.as_mut().unwrap().try_send(contain);
             }
             Message::LinkClicked(link) => {
-                open::that_in_background(&link);
+                let Some(scheme) = link_scheme(&link) else {
+                    tracing::warn!("Ignore the link without a scheme: {link}");
+                    return Command::none();
+                };
+                if self.bar_settings.links.is_allowed(&scheme) {
+                    return self.open_link(link);
+                }
+                // NOTE: the other schemes can start anything, so ask before opening them
+                self.pending_link = Some(link);
+                if self.link_confirm_id.is_some() {
+                    return Command::none();
+                }
+                let id = iced::window::Id::unique();
+                self.set_id_info(id, LaLaInfo::LinkConfirm);
+                return Command::done(Message::NewLayerShell {
+                    settings: NewLayerShellSettings {
+                        size: Some((400, 150)),
+                        exclusive_zone: None,
+                        anchor: Anchor::Right | Anchor::Top,
+                        layer: Layer::Overlay,
+                        margin: Some((10, 10, 10, 10)),
+                        keyboard_interactivity: KeyboardInteractivity::OnDemand,
+                        output_option: OutputOption::LastOutput,
+                        ..Default::default()
+                    },
+                    id,
+                });
+            }
+            Message::ConfirmLink(confirmed) => {
+                let Some(id) = self.link_confirm_id else {
+                    return Command::none();
+                };
+                let close = iced_runtime::task::effect(Action::Window(WindowAction::Close(id)));
+                let Some(link) = self.pending_link.take().filter(|_| confirmed) else {
+                    return close;
+                };
+                return Command::batch([self.open_link(link), close]);
+            }
+            Message::AllowLinkScheme => {
+                let Some(scheme) = self.pending_link.as_deref().and_then(link_scheme) else {
+                    return Command::none();
+                };
+                self.bar_settings.allow_scheme(scheme);
+                self.bar_settings.write_to_file();
+                return Command::done(Message::ConfirmLink(true));
+            }
+            Message::CopyText(text) => {
+                return iced::clipboard::write(text);
+            }
+            Message::ShowNotifyDetails(nid) => {
+                let Some(info) = self.notification_info(nid) else {
+                    return Command::none();
+                };
+                self.details_notification = Some(info.unit.clone());
+                if self.details_id.is_some() {
+                    return Command::none();
+                }
+                let id = iced::window::Id::unique();
+                self.set_id_info(id, LaLaInfo::NotifyDetails);
+                return Command::done(Message::NewLayerShell {
+                    settings: NewLayerShellSettings {
+                        size: Some((400, 500)),
+                        exclusive_zone: None,
+                        anchor: Anchor::Right | Anchor::Top,
+                        layer: Layer::Top,
+                        // NOTE: beside the popups, so the notification is still seen
+                        margin: Some((10, 320, 10, 10)),
+                        keyboard_interactivity: KeyboardInteractivity::OnDemand,
+                        output_option: OutputOption::LastOutput,
+                        ..Default::default()
+                    },
+                    id,
+                });
+            }
+            Message::CloseNotifyDetails => {
+                if let Some(id) = self.details_id {
+                    return iced_runtime::task::effect(Action::Window(WindowAction::Close(id)));
+                }
             }
             Message::WindowClosed(id) => {
                 self.remove_id(id);
@@ -1339,29 +3369,29 @@ impl LalaMusicBar {
                     let btnwidgets: Element<Message> = unitwidgetinfo.notify_button(self);
 
                     let notify = &unitwidgetinfo.unit;
-                    if notify.inline_reply_support() {
-                        return column![
-                            btnwidgets,
-                            Space::new().height(5.),
-                            row![
-                                text_input(
-                                    &fl!("inline-reply-placeholder"),
-                                    &unitwidgetinfo.inline_reply
-                                )
-                                .on_input(move |msg| Message::InlineReplyMsgUpdate((id, msg)))
-                                .on_submit(Message::InlineReply((
-                                    notify.id,
-                                    unitwidgetinfo.inline_reply.clone()
-                                ))),
-                                button(text(fl!("send"))).on_press(Message::InlineReply((
-                                    notify.id,
-                                    unitwidgetinfo.inline_reply.clone()
-                                ))),
-                            ]
-                        ]
-                        .into();
+                    let actions = notify.action_pairs();
+                    if !notify.inline_reply_support() && actions.is_empty() {
+                        return btnwidgets;
+                    }
+                    let mut view_elements: Vec<Element<Message>> = vec![btnwidgets];
+                    if !actions.is_empty() {
+                        let action_btns: Vec<Element<Message>> = actions
+                            .into_iter()
+                            .map(|(action_key, label)| {
+                                button(text(label).shaping(text::Shaping::Advanced))
+                                    .style(button::secondary)
+                                    .on_press(Message::InvokeAction((notify.id, action_key)))
+                                    .into()
+                            })
+                            .collect();
+                        view_elements.push(Space::new().height(5.).into());
+                        view_elements.push(row(action_btns).spacing(5.).into());
                     }
-                    return btnwidgets;
+                    if let Some(editor) = self.reply_editor(notify) {
+                        view_elements.push(Space::new().height(5.).into());
+                        view_elements.push(editor);
+                    }
+                    return column(view_elements).into();
                 }
                 LaLaInfo::HiddenInfo => {
                     let mut args = FluentArgs::new();
@@ -1371,6 +3401,54 @@ impl LalaMusicBar {
                 LaLaInfo::RightPanel => {
                     return self.right_panel_view();
                 }
+                LaLaInfo::MediaPopup => {
+                    return self.media_view();
+                }
+                LaLaInfo::MixerPopup => {
+                    return self.mixer_view();
+                }
+                LaLaInfo::DevicesPopup => {
+                    return self.devices_view();
+                }
+                LaLaInfo::Osd => {
+                    if let Some(osd) = &self.osd {
+                        return osd.view();
+                    }
+                    return Space::new().into();
+                }
+                LaLaInfo::LinkConfirm => {
+                    if let Some(link) = &self.pending_link {
+                        let mut args = FluentArgs::new();
+                        args.set("scheme", link_scheme(link).unwrap_or_default());
+                        return container(
+                            column![
+                                text(fl!("open-link-confirm")),
+                                text(link).shaping(text::Shaping::Advanced),
+                                Space::new().height(Length::Fill),
+                                row![
+                                    button(text(fl!("open-link")))
+                                        .on_press(Message::ConfirmLink(true)),
+                                    button(text(fl!("always-allow-scheme", args)))
+                                        .style(button::secondary)
+                                        .on_press(Message::AllowLinkScheme),
+                                    Space::new().width(Length::Fill),
+                                    button(text(fl!("cancel")))
+                                        .style(button::secondary)
+                                        .on_press(Message::ConfirmLink(false)),
+                                ]
+                                .spacing(5.)
+                            ]
+                            .spacing(5.),
+                        )
+                        .padding(10.)
+                        .into();
+                    }
+                }
+                LaLaInfo::NotifyDetails => {
+                    if let Some(unit) = &self.details_notification {
+                        return notify_details_view(unit);
+                    }
+                }
                 LaLaInfo::ErrorHappened(id) => {
                     tracing::error!("Error happened, for window id: {id:?}");
                     return button(row![
@@ -1392,22 +3470,42 @@ impl LalaMusicBar {
         iced::Subscription::batch([
             iced::window::frames().map(|_| Message::Tick),
             wav_canvars::listen_pw().map(Message::Pw),
-            // NOTE: update the base data
-            iced::time::every(std::time::Duration::from_secs(5)).map(|_| Message::UpdateData),
-            iced::time::every(std::time::Duration::from_secs(1))
-                .map(|_| Message::RequestMprisInfoUpdate),
+            minute_ticks().map(|_| Message::UpdateClock),
+            shutdown_signals().map(|_| Message::Shutdown),
+            zbus_mpirs::listen_changes().map(|_| Message::RequestMprisInfoUpdate),
+            audio::listen_changes().map(|_| Message::VolumeChanged),
             iced::event::listen()
                 .map(|event| Message::LauncherInfo(LaunchMessage::IcedEvent(event))),
             iced::window::close_events().map(Message::WindowClosed),
+            // NOTE: keys pressed inside a notification popup
+            iced::event::listen_with(|event, status, id| match (event, status) {
+                (
+                    iced::Event::Keyboard(iced::keyboard::Event::KeyPressed { key, .. }),
+                    iced::event::Status::Ignored,
+                ) => Some(Message::NotifyKeyPressed((id, key))),
+                _ => None,
+            }),
             // NOTE: dbus connections
             iced::Subscription::run(|| {
                 iced::stream::channel(100, |mut output: Sender<Message>| async move {
                     use iced::futures::sink::SinkExt;
 
                     use dbusbackend::start_backend;
-                    let _backend_conn = start_backend(output.clone())
+                    let backend_conn = start_backend(output.clone())
                         .await
                         .expect("already registered");
+                    let (now_playing_sender, mut now_playing_receiver) = channel(10);
+                    output
+                        .send(Message::BackendReady(now_playing_sender))
+                        .await
+                        .ok();
+                    tokio::spawn(async move {
+                        while let Some(now_playing) = now_playing_receiver.next().await {
+                            dbusbackend::update_now_playing(&backend_conn, now_playing)
+                                .await
+                                .ok();
+                        }
+                    });
 
                     let (sender, mut receiver) = channel(100);
                     let (check_sender, mut check_receiver) = channel(100);
@@ -1,17 +1,36 @@
+use crate::localization::fl;
 use crate::{LalaMusicBar, Message};
-use iced::widget::{Space, button, column, image, markdown, row, svg, text};
+use chrono::{DateTime, Local};
+use iced::widget::{
+    Space, button, column, container, image, markdown, row, scrollable, svg, text, tooltip,
+};
 use iced::{Font, Length};
-use iced_zbus_notification::{ImageInfo, NotifyUnit};
+use iced_aw::ContextMenu;
+use iced_zbus_notification::{ImageInfo, NotifyUnit, Urgency};
 
 #[derive(Debug, Clone, PartialEq)]
 pub struct NotifyUnitWidgetInfo {
     pub to_delete: bool,
     pub upper: i32,
     pub counter: usize,
-    pub inline_reply: String,
     pub unit: NotifyUnit,
 }
 
+/// The replies sent to one conversation, a conversation is a summary of an app, as chat
+/// apps use the summary for the name of the sender
+#[derive(Debug, Clone)]
+pub struct ReplyThread {
+    pub app_name: String,
+    pub summary: String,
+    pub replies: Vec<(DateTime<Local>, String)>,
+}
+
+impl ReplyThread {
+    pub fn is_conversation_of(&self, unit: &NotifyUnit) -> bool {
+        self.app_name == unit.app_name && self.summary == unit.summery
+    }
+}
+
 #[derive(Debug)]
 struct CustomMarkdownView;
 
@@ -19,15 +38,58 @@ impl<'a> markdown::Viewer<'a, Message> for CustomMarkdownView {
     fn on_link_click(uri: markdown::Uri) -> Message {
         Message::LinkClicked(uri)
     }
+
+    // NOTE: spans cannot hold a tooltip, so the targets of the links are shown over the paragraph
+    fn paragraph(
+        &self,
+        settings: markdown::Settings,
+        text: &markdown::Text,
+    ) -> iced::Element<'a, Message> {
+        let paragraph = markdown::paragraph(settings, text, Self::on_link_click);
+        let mut links: Vec<markdown::Uri> = vec![];
+        for link in text
+            .spans(settings.style)
+            .iter()
+            .filter_map(|span| span.link.clone())
+        {
+            if !links.contains(&link) {
+                links.push(link);
+            }
+        }
+        if links.is_empty() {
+            return paragraph;
+        }
+        tooltip(
+            paragraph,
+            container(iced::widget::text(links.join("\n")).size(12))
+                .padding(5.)
+                .style(container::rounded_box),
+            tooltip::Position::Bottom,
+        )
+        .into()
+    }
 }
 
 impl NotifyUnitWidgetInfo {
     pub fn notify_button<'a>(&self, bar: &'a LalaMusicBar) -> iced::Element<'a, Message> {
         let notify = &self.unit;
-        let notify_theme = if notify.is_critical() {
-            button::primary
-        } else {
-            button::secondary
+        let policy = bar.bar_settings.notification_policy(notify.urgency());
+        let accent = policy.accent();
+        let border = policy.border();
+        let is_critical = notify.is_critical();
+        let notify_theme = move |theme: &iced::Theme, status| {
+            let mut style = if is_critical {
+                button::primary(theme, status)
+            } else {
+                button::secondary(theme, status)
+            };
+            if let Some(accent) = accent {
+                style.background = Some(iced::Background::Color(accent));
+            }
+            if let Some(border) = border {
+                style.border = style.border.color(border).width(2);
+            }
+            style
         };
 
         let markdown_info = bar.notifications_markdown.get(&self.unit.id);
@@ -54,7 +116,7 @@ impl NotifyUnitWidgetInfo {
             .height(Length::Fill)
             .on_press(Message::RemoveNotify(self.unit.id));
 
-        match notify.image() {
+        let notify_btn: iced::Element<'a, Message> = match notify.image() {
             Some(ImageInfo::Svg(path)) => button(row![
                 svg(svg::Handle::from_path(path))
                     .height(Length::Fill)
@@ -121,7 +183,7 @@ impl NotifyUnitWidgetInfo {
             ])
             .width(Length::Fill)
             .height(Length::Fill)
-            .style(button::secondary)
+            .style(notify_theme)
             .clip(true)
             .on_press(Message::RemoveNotify(self.unit.id))
             .into(),
@@ -135,10 +197,110 @@ impl NotifyUnitWidgetInfo {
             .clip(true)
             .on_press(Message::RemoveNotify(self.unit.id))
             .into(),
-        }
+        };
+
+        let id = notify.id;
+        let summery = notify.summery.clone();
+        let body = notify.body.clone();
+        ContextMenu::new(notify_btn, move || {
+            let menu_button = |label: String, message: Message| {
+                button(text(label))
+                    .style(button::text)
+                    .width(Length::Fill)
+                    .on_press(message)
+            };
+            container(column![
+                menu_button(fl!("copy-summary"), Message::CopyText(summery.clone())),
+                menu_button(fl!("copy-body"), Message::CopyText(body.clone())),
+                menu_button(fl!("details"), Message::ShowNotifyDetails(id)),
+            ])
+            .width(Length::Fixed(150.))
+            .style(container::rounded_box)
+            .into()
+        })
+        .into()
     }
 }
 
+/// Every raw field and hint of the notification, as `(name, value)`
+pub fn notify_details_fields(unit: &NotifyUnit) -> Vec<(String, String)> {
+    let hint = &unit.hint;
+    let urgency = match unit.urgency() {
+        Urgency::Low => "low",
+        Urgency::Normal => "normal",
+        Urgency::Critical => "critical",
+    };
+    let actions = unit
+        .actions
+        .chunks(2)
+        .map(|pair| pair.join(": "))
+        .collect::<Vec<String>>()
+        .join("\n");
+    let received: DateTime<Local> = unit.timestamp.into();
+    let mut fields = vec![
+        (fl!("detail-app-name"), unit.app_name.clone()),
+        (fl!("detail-id"), unit.id.to_string()),
+        (fl!("detail-summary"), unit.summery.clone()),
+        (fl!("detail-body"), unit.body.clone()),
+        (fl!("detail-icon"), unit.icon.clone()),
+        (fl!("detail-actions"), actions),
+        (fl!("detail-timeout"), unit.timeout.to_string()),
+        (
+            fl!("detail-received"),
+            received.format("%Y-%m-%d %H:%M:%S").to_string(),
+        ),
+        (fl!("detail-urgency"), urgency.to_owned()),
+        (
+            fl!("detail-category"),
+            hint.category().unwrap_or_default().to_owned(),
+        ),
+        (
+            fl!("detail-desktop-entry"),
+            hint.desktop_entry().unwrap_or_default().to_owned(),
+        ),
+        (
+            fl!("detail-image-data"),
+            hint.image_size()
+                .map(|(width, height)| format!("{width}x{height}"))
+                .unwrap_or_default(),
+        ),
+    ];
+    fields.extend(hint.raw_hints().iter().cloned());
+    fields
+}
+
+pub fn notify_details_view(unit: &NotifyUnit) -> iced::Element<'_, Message> {
+    let fields = notify_details_fields(unit);
+    let all_text = fields
+        .iter()
+        .map(|(name, value)| format!("{name}: {value}"))
+        .collect::<Vec<String>>()
+        .join("\n");
+    let rows: Vec<iced::Element<Message>> = fields
+        .into_iter()
+        .map(|(name, value)| {
+            column![
+                text(name).size(12).font(Font {
+                    weight: iced::font::Weight::Bold,
+                    ..Default::default()
+                }),
+                text(value).shaping(text::Shaping::Advanced),
+            ]
+            .into()
+        })
+        .collect();
+    column![
+        scrollable(column(rows).spacing(8.).padding(10.)).height(Length::Fill),
+        row![
+            button(text(fl!("copy-all"))).on_press(Message::CopyText(all_text)),
+            Space::new().width(Length::Fill),
+            button(text(fl!("close"))).on_press(Message::CloseNotifyDetails),
+        ]
+        .padding(10.)
+    ]
+    .into()
+}
+
 #[allow(unused)]
 #[derive(Debug)]
 pub enum NotifyCommand {
@@ -146,3 +308,8 @@ pub enum NotifyCommand {
     InlineReply { id: u32, text: String },
     NotificationClosed { id: u32, reason: u32 },
 }
+
+/// The id of the inline reply input inside the popup of notification `id`
+pub fn inline_reply_id(id: u32) -> iced::widget::Id {
+    iced::widget::Id::from(format!("inline-reply-{id}"))
+}
@@ -0,0 +1,114 @@
+use std::collections::HashMap;
+use std::path::PathBuf;
+use std::sync::LazyLock;
+
+use iced::widget::{column, container, image, progress_bar, row, svg, text};
+use iced::{Alignment, Element, Length};
+
+use crate::Message;
+use crate::audio::Volume;
+use crate::launcher::get_icon_path_from_xdgicon;
+
+// NOTE: the volume is shown on every change, so its icons are looked up only once
+static VOLUME_ICONS: LazyLock<HashMap<&'static str, Option<PathBuf>>> = LazyLock::new(|| {
+    [
+        "audio-volume-muted",
+        "audio-volume-low",
+        "audio-volume-medium",
+        "audio-volume-high",
+    ]
+    .into_iter()
+    .map(|name| (name, get_icon_path_from_xdgicon(name)))
+    .collect()
+});
+
+/// What the on-screen display shows, like the volume after a key press
+#[derive(Debug, Clone, PartialEq)]
+pub struct Osd {
+    icon: Option<PathBuf>,
+    /// Shown when there is no icon, it can be an emoji
+    glyph: String,
+    /// From 0 to 1, None to show only the label
+    value: Option<f32>,
+    label: String,
+}
+
+impl Osd {
+    /// The icon is an icon name, or the text shown instead if there is no such icon.
+    /// A negative value hides the progress bar
+    pub fn new(icon: &str, value: f64, label: String) -> Self {
+        Self {
+            icon: get_icon_path_from_xdgicon(icon),
+            glyph: icon.to_owned(),
+            value: (value >= 0.).then_some(value.min(1.) as f32),
+            label,
+        }
+    }
+
+    pub fn volume(volume: &Volume) -> Self {
+        let percent = volume.channels.iter().copied().max().unwrap_or(0);
+        let (icon, glyph) = volume_icon(percent, volume.mute);
+        Self {
+            icon: VOLUME_ICONS.get(icon).cloned().flatten(),
+            glyph: glyph.to_owned(),
+            value: Some((percent as f32 / 100.).clamp(0., 1.)),
+            label: format!("{percent}%"),
+        }
+    }
+
+    pub fn view(&self) -> Element<'_, Message> {
+        let icon: Element<'_, Message> = match &self.icon {
+            Some(path) if path.extension().is_some_and(|extension| extension == "png") => {
+                image(image::Handle::from_path(path))
+                    .width(Length::Fixed(40.))
+                    .height(Length::Fixed(40.))
+                    .into()
+            }
+            Some(path) => svg(svg::Handle::from_path(path))
+                .width(Length::Fixed(40.))
+                .height(Length::Fixed(40.))
+                .into(),
+            None => text(&self.glyph)
+                .size(30)
+                .shaping(text::Shaping::Advanced)
+                .into(),
+        };
+        let mut info = column![text(&self.label).shaping(text::Shaping::Advanced)].spacing(5.);
+        if let Some(value) = self.value {
+            info = info.push(progress_bar(0.0..=1.0, value).girth(8.));
+        }
+        container(
+            row![icon, info.width(Length::Fill)]
+                .spacing(15.)
+                .align_y(Alignment::Center),
+        )
+        .padding(15.)
+        .center_y(Length::Fill)
+        .style(container::rounded_box)
+        .into()
+    }
+}
+
+/// The icon name, and the glyph used without an icon theme
+fn volume_icon(percent: i64, mute: bool) -> (&'static str, &'static str) {
+    match percent {
+        _ if mute || percent <= 0 => ("audio-volume-muted", "🔇"),
+        1..=33 => ("audio-volume-low", "🔈"),
+        34..=66 => ("audio-volume-medium", "🔉"),
+        _ => ("audio-volume-high", "🔊"),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn volume_icons() {
+        assert_eq!(volume_icon(50, true).0, "audio-volume-muted");
+        assert_eq!(volume_icon(0, false).0, "audio-volume-muted");
+        assert_eq!(volume_icon(20, false).0, "audio-volume-low");
+        assert_eq!(volume_icon(50, false).0, "audio-volume-medium");
+        assert_eq!(volume_icon(120, false).0, "audio-volume-high");
+    }
+}
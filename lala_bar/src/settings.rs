@@ -1,5 +1,11 @@
+use std::collections::BTreeMap;
 use std::io::{Read, Write};
 
+use crate::links::WEB_SCHEMES;
+use crate::zbus_mpirs::{ServiceInfo, player_name};
+
+use iced_layershell::reexport::Layer;
+use iced_zbus_notification::Urgency;
 use serde::{Deserialize, Serialize};
 
 #[derive(Debug, Deserialize, Serialize, PartialEq, Eq, Default)]
@@ -7,6 +13,299 @@ pub struct SettingsConfig {
     pub(crate) background_color: Option<String>,
     #[serde(default)]
     pub(crate) spectrum_chart: bool,
+    #[serde(default)]
+    pub(crate) notification: NotificationPolicy,
+    #[serde(default)]
+    pub(crate) links: LinkPolicy,
+    #[serde(default)]
+    pub(crate) media: MediaPolicy,
+    #[serde(default)]
+    pub(crate) lyrics: LyricsPolicy,
+    #[serde(default)]
+    pub(crate) history: HistoryPolicy,
+    #[serde(default)]
+    pub(crate) microphone: MicrophonePolicy,
+    #[serde(default)]
+    pub(crate) osd: OsdPolicy,
+    #[serde(default)]
+    pub(crate) alsa: AlsaPolicy,
+}
+
+/// How the popups of one urgency level are presented
+#[derive(Debug, Deserialize, Serialize, PartialEq, Eq, Clone, Default)]
+pub struct UrgencyPolicy {
+    /// Show the popup as a banner on every output in [`NotificationPolicy::outputs`]
+    #[serde(default)]
+    pub(crate) all_outputs: bool,
+    /// Use `Layer::Overlay`, so the popup is shown above fullscreen windows
+    #[serde(default)]
+    pub(crate) overlay: bool,
+    /// Ignore the timeout, the popup stays until it is dismissed
+    #[serde(default)]
+    pub(crate) sticky: bool,
+    pub(crate) border_color: Option<String>,
+    pub(crate) accent_color: Option<String>,
+    /// Never put the popup into the hidden list, even in quiet mode
+    #[serde(default)]
+    pub(crate) bypass_hidden: bool,
+}
+
+impl UrgencyPolicy {
+    fn critical() -> Self {
+        Self {
+            sticky: true,
+            ..Default::default()
+        }
+    }
+
+    pub fn layer(&self) -> Layer {
+        if self.overlay {
+            Layer::Overlay
+        } else {
+            Layer::Top
+        }
+    }
+
+    pub fn border(&self) -> Option<iced::Color> {
+        parse_color(self.border_color.as_ref()?)
+    }
+
+    pub fn accent(&self) -> Option<iced::Color> {
+        parse_color(self.accent_color.as_ref()?)
+    }
+}
+
+#[derive(Debug, Deserialize, Serialize, PartialEq, Eq)]
+pub struct NotificationPolicy {
+    /// NOTE: layershell cannot list the outputs, so the outputs used by `all_outputs` are set here
+    #[serde(default)]
+    pub(crate) outputs: Vec<String>,
+    #[serde(default)]
+    pub(crate) low: UrgencyPolicy,
+    #[serde(default)]
+    pub(crate) normal: UrgencyPolicy,
+    #[serde(default = "UrgencyPolicy::critical")]
+    pub(crate) critical: UrgencyPolicy,
+}
+
+impl Default for NotificationPolicy {
+    fn default() -> Self {
+        Self {
+            outputs: Vec::new(),
+            low: UrgencyPolicy::default(),
+            normal: UrgencyPolicy::default(),
+            critical: UrgencyPolicy::critical(),
+        }
+    }
+}
+
+impl NotificationPolicy {
+    pub fn policy(&self, urgency: Urgency) -> &UrgencyPolicy {
+        match urgency {
+            Urgency::Low => &self.low,
+            Urgency::Normal => &self.normal,
+            Urgency::Critical => &self.critical,
+        }
+    }
+
+    /// The outputs the banners of this urgency are shown on, empty if they follow the other popups
+    pub fn banner_outputs(&self, urgency: Urgency) -> &[String] {
+        if self.policy(urgency).all_outputs {
+            &self.outputs
+        } else {
+            &[]
+        }
+    }
+}
+
+/// How the links inside the notifications are opened
+#[derive(Debug, Deserialize, Serialize, PartialEq, Eq)]
+pub struct LinkPolicy {
+    /// Open the links through the OpenURI portal, xdg-open is used if it is off or fails
+    #[serde(default = "default_use_portal")]
+    pub(crate) use_portal: bool,
+    /// The schemes opened without confirmation, http and https are always allowed
+    #[serde(default)]
+    pub(crate) allowed_schemes: Vec<String>,
+    /// The commands to open the schemes with, `{uri}` is replaced by the link
+    #[serde(default)]
+    pub(crate) handlers: BTreeMap<String, String>,
+}
+
+fn default_use_portal() -> bool {
+    true
+}
+
+impl Default for LinkPolicy {
+    fn default() -> Self {
+        Self {
+            use_portal: default_use_portal(),
+            allowed_schemes: Vec::new(),
+            handlers: BTreeMap::new(),
+        }
+    }
+}
+
+impl LinkPolicy {
+    pub fn is_allowed(&self, scheme: &str) -> bool {
+        WEB_SCHEMES.contains(&scheme)
+            || self
+                .allowed_schemes
+                .iter()
+                .any(|allowed| allowed.eq_ignore_ascii_case(scheme))
+    }
+
+    pub fn handler(&self, scheme: &str) -> Option<&str> {
+        self.handlers
+            .iter()
+            .find(|(handled, _)| handled.eq_ignore_ascii_case(scheme))
+            .map(|(_, handler)| handler.as_str())
+    }
+}
+
+const DEFAULT_TITLE_FORMAT: &str = "{title}[ — {artist}]";
+
+/// Which MPRIS player is shown, the players are matched by identity or bus name
+#[derive(Debug, Deserialize, Serialize, PartialEq, Eq, Clone, Default)]
+pub struct MediaPolicy {
+    /// The player always shown while it is running
+    pub(crate) pinned: Option<String>,
+    /// The players preferred first when none is pinned, the unlisted ones come last
+    #[serde(default)]
+    pub(crate) priority: Vec<String>,
+    /// The players never shown
+    #[serde(default)]
+    pub(crate) blacklist: Vec<String>,
+    /// Show a notification when the track of the shown player changes
+    #[serde(default)]
+    pub(crate) track_notification: bool,
+    /// How the track is shown on the bar, see [`crate::zbus_mpirs::Metadata::format`]
+    pub(crate) title_format: Option<String>,
+}
+
+impl MediaPolicy {
+    pub fn is_blacklisted(&self, info: &ServiceInfo) -> bool {
+        self.blacklist.iter().any(|name| info.is_player(name))
+    }
+
+    /// Like [`Self::is_blacklisted`], for the players which are only known by bus name
+    pub fn is_blacklisted_name(&self, service_path: &str) -> bool {
+        self.blacklist.iter().any(|name| {
+            name.eq_ignore_ascii_case(player_name(service_path)) || name == service_path
+        })
+    }
+
+    pub fn priority_of(&self, info: &ServiceInfo) -> usize {
+        self.priority
+            .iter()
+            .position(|name| info.is_player(name))
+            .unwrap_or(self.priority.len())
+    }
+
+    pub fn title_format(&self) -> &str {
+        self.title_format.as_deref().unwrap_or(DEFAULT_TITLE_FORMAT)
+    }
+
+    pub fn is_pinned(&self, info: &ServiceInfo) -> bool {
+        self.pinned
+            .as_deref()
+            .is_some_and(|name| info.is_player(name))
+    }
+}
+
+/// Where the lyrics from `.lrc` files are shown
+#[derive(Debug, Deserialize, Serialize, PartialEq, Eq, Clone, Default)]
+pub struct LyricsPolicy {
+    /// Show the current line instead of the title on the bar
+    #[serde(default)]
+    pub(crate) bar: bool,
+    /// Show the current and the next line in the media popup
+    #[serde(default)]
+    pub(crate) popup: bool,
+    /// The directory of `artist - title.lrc` files, used when there is none next to the track
+    pub(crate) directory: Option<String>,
+}
+
+impl LyricsPolicy {
+    pub fn enabled(&self) -> bool {
+        self.bar || self.popup
+    }
+}
+
+/// The listening history, kept in `$XDG_DATA_HOME/lala-bar/history.jsonl`
+#[derive(Debug, Deserialize, Serialize, PartialEq, Eq, Clone, Default)]
+pub struct HistoryPolicy {
+    /// Record the tracks listened to, with more than half of them or 4 minutes played
+    #[serde(default)]
+    pub(crate) enabled: bool,
+}
+
+/// The microphone module and the indicator of the applications capturing sound
+#[derive(Debug, Deserialize, Serialize, PartialEq, Eq, Clone, Default)]
+pub struct MicrophonePolicy {
+    /// Count the capture of the spectrum chart as an application capturing sound
+    #[serde(default)]
+    pub(crate) show_spectrum_capture: bool,
+}
+
+/// The on-screen display, shown in the center of the output
+#[derive(Debug, Deserialize, Serialize, PartialEq, Eq, Clone)]
+pub struct OsdPolicy {
+    /// Show the volume whenever the default output changes
+    #[serde(default = "default_osd_volume")]
+    pub(crate) volume: bool,
+    /// How long it is shown, in milliseconds
+    #[serde(default = "default_osd_timeout")]
+    pub(crate) timeout: u64,
+}
+
+fn default_osd_volume() -> bool {
+    true
+}
+
+fn default_osd_timeout() -> u64 {
+    1500
+}
+
+impl Default for OsdPolicy {
+    fn default() -> Self {
+        Self {
+            volume: default_osd_volume(),
+            timeout: default_osd_timeout(),
+        }
+    }
+}
+
+/// The ALSA control used without PipeWire
+#[derive(Debug, Deserialize, Serialize, PartialEq, Eq, Clone)]
+pub struct AlsaPolicy {
+    /// Like "default" or "hw:1"
+    #[serde(default = "default_alsa_card")]
+    pub(crate) card: String,
+    #[serde(default = "default_alsa_control")]
+    pub(crate) control: String,
+}
+
+fn default_alsa_card() -> String {
+    "default".to_owned()
+}
+
+fn default_alsa_control() -> String {
+    "Master".to_owned()
+}
+
+impl Default for AlsaPolicy {
+    fn default() -> Self {
+        Self {
+            card: default_alsa_card(),
+            control: default_alsa_control(),
+        }
+    }
+}
+
+fn parse_color(color: &str) -> Option<iced::Color> {
+    let color = csscolorparser::parse(color).ok()?;
+    Some(iced::Color::from_rgba(color.r, color.g, color.b, color.a))
 }
 
 fn ensure_file() {
@@ -69,6 +368,14 @@ impl SettingsConfig {
         let color = csscolorparser::parse(background_color).ok()?;
         Some(iced::Color::from_rgb(color.r, color.g, color.b))
     }
+    pub fn notification_policy(&self, urgency: Urgency) -> &UrgencyPolicy {
+        self.notification.policy(urgency)
+    }
+    pub fn allow_scheme(&mut self, scheme: String) {
+        if !self.links.is_allowed(&scheme) {
+            self.links.allowed_schemes.push(scheme);
+        }
+    }
     pub fn spectrum_enable(&self) -> bool {
         self.spectrum_chart
     }
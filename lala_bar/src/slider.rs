@@ -1,24 +1,51 @@
-#[derive(Copy, Clone, Default)]
+#[derive(Copy, Clone, Default, Debug, PartialEq, Eq)]
 pub enum SliderIndex {
     #[default]
     Balance,
-    Left,
-    Right,
+    /// One channel of the output, by its index
+    Channel(usize),
 }
 
 impl SliderIndex {
-    pub fn next(&self) -> Self {
-        match self {
-            SliderIndex::Balance => SliderIndex::Left,
-            SliderIndex::Left => SliderIndex::Right,
-            SliderIndex::Right => SliderIndex::Balance,
+    pub fn next(&self, channels: usize) -> Self {
+        match *self {
+            SliderIndex::Balance if channels > 0 => SliderIndex::Channel(0),
+            SliderIndex::Channel(index) if index + 1 < channels => SliderIndex::Channel(index + 1),
+            _ => SliderIndex::Balance,
         }
     }
-    pub fn pre(&self) -> Self {
-        match self {
-            SliderIndex::Balance => SliderIndex::Right,
-            SliderIndex::Left => SliderIndex::Balance,
-            SliderIndex::Right => SliderIndex::Left,
+    pub fn pre(&self, channels: usize) -> Self {
+        match *self {
+            SliderIndex::Balance if channels > 0 => SliderIndex::Channel(channels - 1),
+            SliderIndex::Channel(index) if index > 0 && index <= channels => {
+                SliderIndex::Channel(index - 1)
+            }
+            _ => SliderIndex::Balance,
         }
     }
+    /// Back to the balance if the channel is gone, like after the output changed
+    pub fn fit(&self, channels: usize) -> Self {
+        match *self {
+            SliderIndex::Channel(index) if index >= channels => SliderIndex::Balance,
+            index => index,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn cycle_channels() {
+        let index = SliderIndex::Balance;
+        assert_eq!(index.next(2), SliderIndex::Channel(0));
+        assert_eq!(index.next(2).next(2), SliderIndex::Channel(1));
+        assert_eq!(index.next(2).next(2).next(2), SliderIndex::Balance);
+        assert_eq!(index.pre(6), SliderIndex::Channel(5));
+        assert_eq!(SliderIndex::Channel(0).pre(6), SliderIndex::Balance);
+        assert_eq!(index.next(0), SliderIndex::Balance);
+        assert_eq!(SliderIndex::Channel(5).fit(2), SliderIndex::Balance);
+        assert_eq!(SliderIndex::Channel(1).fit(2), SliderIndex::Channel(1));
+    }
 }
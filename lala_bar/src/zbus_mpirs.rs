@@ -1,6 +1,7 @@
 use std::{
     collections::HashMap,
     sync::{Arc, OnceLock},
+    time::{Duration, Instant},
 };
 
 use futures_util::StreamExt;
@@ -13,101 +14,687 @@ use zbus::{
     Result,
     fdo::{DBusProxy, NameOwnerChangedArgs},
     proxy,
-    zvariant::{OwnedObjectPath, OwnedValue},
+    proxy::PropertyStream,
+    zvariant::{ObjectPath, OwnedObjectPath, OwnedValue, Value},
 };
 
 const PLAYCTLD: &str = "org.mpris.MediaPlayer2.playerctld";
 
-#[allow(unused)]
-#[derive(Debug, Clone)]
+const MPRIS_PREFIX: &str = "org.mpris.MediaPlayer2.";
+
+const NO_TRACK: &str = "/org/mpris/MediaPlayer2/TrackList/NoTrack";
+
+const MAX_PLAYLISTS: u32 = 100;
+
+// NOTE: players do not always send the types in the spec, like a string instead of a list of
+// strings, or an unsigned length, so the values are converted by what they hold
+fn value_to_string(value: &Value<'_>) -> Option<String> {
+    match value {
+        Value::Str(text) => Some(text.to_string()),
+        Value::ObjectPath(path) => Some(path.to_string()),
+        Value::Value(value) => value_to_string(value),
+        Value::Array(list) => list.iter().find_map(value_to_string),
+        _ => None,
+    }
+}
+
+fn value_to_strings(value: &Value<'_>) -> Vec<String> {
+    match value {
+        Value::Array(list) => list.iter().filter_map(value_to_string).collect(),
+        Value::Value(value) => value_to_strings(value),
+        value => value_to_string(value).into_iter().collect(),
+    }
+}
+
+fn value_to_int(value: &Value<'_>) -> Option<i64> {
+    match value {
+        Value::U8(number) => Some(i64::from(*number)),
+        Value::I16(number) => Some(i64::from(*number)),
+        Value::U16(number) => Some(i64::from(*number)),
+        Value::I32(number) => Some(i64::from(*number)),
+        Value::U32(number) => Some(i64::from(*number)),
+        Value::I64(number) => Some(*number),
+        Value::U64(number) => i64::try_from(*number).ok(),
+        Value::F64(number) => Some(*number as i64),
+        Value::Str(text) => text.trim().parse().ok(),
+        Value::Value(value) => value_to_int(value),
+        _ => None,
+    }
+}
+
+fn value_to_float(value: &Value<'_>) -> Option<f64> {
+    match value {
+        Value::F64(number) => Some(*number),
+        Value::Str(text) => text.trim().parse().ok(),
+        Value::Value(value) => value_to_float(value),
+        value => value_to_int(value).map(|number| number as f64),
+    }
+}
+
+/// The metadata of a track, with all the fields in the MPRIS spec
+#[derive(Debug, Clone, Default)]
 pub struct Metadata {
     mpris_trackid: OwnedObjectPath,
+    mpris_art_url: Option<String>,
+    /// The art is loaded in background, see [`update_art`]
     pub mpris_image: Option<image::Handle>,
+    // NOTE: true while the art of the url is loading, the image may be the one of the last track
+    art_pending: bool,
     pub xesam_title: String,
     xesam_album: String,
+    xesam_album_artist: Vec<String>,
     xesam_artist: Vec<String>,
+    xesam_as_text: Option<String>,
+    xesam_audio_bpm: Option<i64>,
+    xesam_auto_rating: Option<f64>,
+    xesam_comment: Vec<String>,
+    xesam_composer: Vec<String>,
+    xesam_content_created: Option<String>,
+    xesam_disc_number: Option<i64>,
+    xesam_first_used: Option<String>,
+    xesam_genre: Vec<String>,
+    xesam_last_used: Option<String>,
+    xesam_lyricist: Vec<String>,
+    xesam_track_number: Option<i64>,
+    xesam_url: Option<String>,
+    xesam_use_count: Option<i64>,
+    xesam_user_rating: Option<f64>,
+    /// The length of the track in microseconds
+    pub mpris_length: Option<i64>,
 }
 
 impl Metadata {
-    fn from_hashmap(mut value: HashMap<String, OwnedValue>) -> Self {
-        let art_url = value.remove("mpris:artUrl");
-        let mut mpris_image = None;
-        if let Some(art_url) = art_url {
-            let mpris_arturl: String = art_url.try_into().unwrap_or_default();
-            if let Some(art_url) = url::Url::parse(&mpris_arturl)
-                .ok()
-                .and_then(|url| url.to_file_path().ok())
-            {
-                use std::fs;
-                if let Ok(data) = fs::read(art_url) {
-                    mpris_image = Some(image::Handle::from_bytes(data));
+    fn from_hashmap(value: HashMap<String, OwnedValue>) -> Self {
+        let string = |key: &str| value.get(key).and_then(|value| value_to_string(value));
+        let strings = |key: &str| {
+            value
+                .get(key)
+                .map(|value| value_to_strings(value))
+                .unwrap_or_default()
+        };
+        let int = |key: &str| value.get(key).and_then(|value| value_to_int(value));
+        let float = |key: &str| value.get(key).and_then(|value| value_to_float(value));
+
+        let mpris_art_url = string("mpris:artUrl").filter(|url| !url.is_empty());
+        let mpris_trackid = string("mpris:trackid")
+            .and_then(|id| OwnedObjectPath::try_from(id).ok())
+            .unwrap_or_default();
+
+        Self {
+            mpris_trackid,
+            art_pending: mpris_art_url.is_some(),
+            mpris_art_url,
+            mpris_image: None,
+            xesam_title: string("xesam:title").unwrap_or_default(),
+            xesam_album: string("xesam:album").unwrap_or_default(),
+            xesam_album_artist: strings("xesam:albumArtist"),
+            xesam_artist: strings("xesam:artist"),
+            xesam_as_text: string("xesam:asText"),
+            xesam_audio_bpm: int("xesam:audioBPM"),
+            xesam_auto_rating: float("xesam:autoRating"),
+            xesam_comment: strings("xesam:comment"),
+            xesam_composer: strings("xesam:composer"),
+            xesam_content_created: string("xesam:contentCreated"),
+            xesam_disc_number: int("xesam:discNumber"),
+            xesam_first_used: string("xesam:firstUsed"),
+            xesam_genre: strings("xesam:genre"),
+            xesam_last_used: string("xesam:lastUsed"),
+            xesam_lyricist: strings("xesam:lyricist"),
+            xesam_track_number: int("xesam:trackNumber"),
+            xesam_url: string("xesam:url"),
+            xesam_use_count: int("xesam:useCount"),
+            xesam_user_rating: float("xesam:userRating"),
+            mpris_length: int("mpris:length"),
+        }
+    }
+
+    pub fn artists(&self) -> &[String] {
+        &self.xesam_artist
+    }
+
+    pub fn album(&self) -> &str {
+        &self.xesam_album
+    }
+
+    pub fn album_artists(&self) -> &[String] {
+        &self.xesam_album_artist
+    }
+
+    /// The lyrics of the track as plain text
+    pub fn as_text(&self) -> Option<&str> {
+        self.xesam_as_text.as_deref()
+    }
+
+    pub fn audio_bpm(&self) -> Option<i64> {
+        self.xesam_audio_bpm
+    }
+
+    /// The rating computed by the player, from 0.0 to 1.0
+    pub fn auto_rating(&self) -> Option<f64> {
+        self.xesam_auto_rating
+    }
+
+    pub fn comments(&self) -> &[String] {
+        &self.xesam_comment
+    }
+
+    pub fn composers(&self) -> &[String] {
+        &self.xesam_composer
+    }
+
+    /// When the track was created, as an ISO 8601 date
+    pub fn content_created(&self) -> Option<&str> {
+        self.xesam_content_created.as_deref()
+    }
+
+    pub fn disc_number(&self) -> Option<i64> {
+        self.xesam_disc_number
+    }
+
+    pub fn first_used(&self) -> Option<&str> {
+        self.xesam_first_used.as_deref()
+    }
+
+    pub fn genres(&self) -> &[String] {
+        &self.xesam_genre
+    }
+
+    pub fn last_used(&self) -> Option<&str> {
+        self.xesam_last_used.as_deref()
+    }
+
+    pub fn lyricists(&self) -> &[String] {
+        &self.xesam_lyricist
+    }
+
+    pub fn track_number(&self) -> Option<i64> {
+        self.xesam_track_number
+    }
+
+    pub fn use_count(&self) -> Option<i64> {
+        self.xesam_use_count
+    }
+
+    /// The rating given by the user, from 0.0 to 1.0
+    pub fn user_rating(&self) -> Option<f64> {
+        self.xesam_user_rating
+    }
+
+    /// The value of the field named in a format string, empty if the track does not have it
+    fn field(&self, name: &str) -> String {
+        let number = |number: Option<i64>| number.map(|number| number.to_string());
+        let rating = |rating: Option<f64>| rating.map(|rating| format!("{:.0}", rating * 5.));
+        let value = match name {
+            "title" => Some(self.xesam_title.clone()),
+            "artist" => Some(self.artists().join(", ")),
+            "album" => Some(self.album().to_owned()),
+            "album_artist" => Some(self.album_artists().join(", ")),
+            "composer" => Some(self.composers().join(", ")),
+            "lyricist" => Some(self.lyricists().join(", ")),
+            "genre" => Some(self.genres().join(", ")),
+            "comment" => Some(self.comments().join(" ")),
+            "track_number" => number(self.track_number()),
+            "disc_number" => number(self.disc_number()),
+            "bpm" => number(self.audio_bpm()),
+            "use_count" => number(self.use_count()),
+            "year" => self
+                .content_created()
+                .map(|date| date.chars().take(4).collect()),
+            "created" => self.content_created().map(str::to_owned),
+            "first_used" => self.first_used().map(str::to_owned),
+            "last_used" => self.last_used().map(str::to_owned),
+            "rating" => rating(self.user_rating().or(self.auto_rating())),
+            "length" => self
+                .mpris_length
+                .map(|length| format!("{}:{:02}", length / 60_000_000, length / 1_000_000 % 60)),
+            "url" => self.url().map(str::to_owned),
+            "lyrics" => self.as_text().map(str::to_owned),
+            _ => None,
+        };
+        value.unwrap_or_default()
+    }
+
+    /// Format the track, like `{artist} - {title}[ ({album})]`
+    ///
+    /// The fields are in braces, the text in brackets is dropped if a field inside it is empty
+    pub fn format(&self, format: &str) -> String {
+        let mut output = String::new();
+        // NOTE: the text of the bracket being read, and if all the fields in it have values
+        let mut section: Option<(String, bool)> = None;
+        let mut chars = format.chars();
+        while let Some(c) = chars.next() {
+            match c {
+                '[' if section.is_none() => section = Some((String::new(), true)),
+                ']' if section.is_some() => {
+                    if let Some((text, true)) = section.take() {
+                        output.push_str(&text);
+                    }
+                }
+                '{' => {
+                    let name: String = chars.by_ref().take_while(|c| *c != '}').collect();
+                    let value = self.field(name.trim());
+                    match &mut section {
+                        Some((text, filled)) => {
+                            *filled &= !value.is_empty();
+                            text.push_str(&value);
+                        }
+                        None => output.push_str(&value),
+                    }
                 }
+                c => match &mut section {
+                    Some((text, _)) => text.push(c),
+                    None => output.push(c),
+                },
             }
         }
+        if let Some((text, true)) = section {
+            output.push_str(&text);
+        }
+        output
+    }
 
-        let trackid = value.remove("mpris:trackid");
-        let mpris_trackid: OwnedObjectPath = trackid
-            .and_then(|id| id.try_into().ok())
-            .unwrap_or_default();
+    /// The art of the track is still loading
+    pub fn art_is_pending(&self) -> bool {
+        self.art_pending
+    }
 
-        let title = value.remove("xesam:title");
-        let xesam_title: String = title
-            .and_then(|title| title.try_into().ok())
-            .unwrap_or_default();
+    pub fn url(&self) -> Option<&str> {
+        self.xesam_url.as_deref()
+    }
+
+    pub fn track_id(&self) -> &str {
+        self.mpris_trackid.as_str()
+    }
+
+    fn has_track(&self) -> bool {
+        let trackid = self.mpris_trackid.as_str();
+        trackid != "/" && trackid != NO_TRACK
+    }
+}
+
+/// A playlist from the Playlists interface of the player
+#[derive(Debug, Clone)]
+pub struct Playlist {
+    id: OwnedObjectPath,
+    pub name: String,
+}
+
+impl Playlist {
+    pub fn id(&self) -> &str {
+        self.id.as_str()
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum LoopStatus {
+    None,
+    Track,
+    Playlist,
+}
 
-        let mut xesam_artist = vec![];
-        if let Some(artist) = value.remove("xesam:artist") {
-            xesam_artist = artist.try_into().unwrap_or_default();
+impl LoopStatus {
+    fn from_property(status: &str) -> Option<Self> {
+        match status {
+            "None" => Some(Self::None),
+            "Track" => Some(Self::Track),
+            "Playlist" => Some(Self::Playlist),
+            _ => None,
         }
+    }
 
-        let mut xesam_album = String::new();
-        let album = value.remove("xesam:album");
-        if let Some(album) = album {
-            xesam_album = album.try_into().unwrap_or_default();
+    fn as_property(&self) -> &'static str {
+        match self {
+            Self::None => "None",
+            Self::Track => "Track",
+            Self::Playlist => "Playlist",
         }
+    }
 
-        Self {
-            mpris_trackid,
-            xesam_title,
-            xesam_artist,
-            xesam_album,
-            mpris_image,
+    /// The status after clicking the loop button, None -> Playlist -> Track -> None
+    pub fn next(&self) -> Self {
+        match self {
+            Self::None => Self::Playlist,
+            Self::Playlist => Self::Track,
+            Self::Track => Self::None,
         }
     }
 }
 
+/// How well the player is connected, see [`unavailable_players`] for the ones which are not
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ConnectionState {
+    Connected,
+    /// Some properties cannot be read, or some signals are not watched any more
+    Degraded,
+    /// The player cannot be connected, it is tried again later
+    Retrying {
+        attempt: u32,
+    },
+    /// The player cannot be connected after all the retries
+    Gone,
+}
+
 #[derive(Debug, Clone)]
 pub struct ServiceInfo {
     service_path: String,
+    pub state: ConnectionState,
+    /// The name of the player from the root interface, like `Spotify`
+    pub identity: String,
+    pub can_raise: bool,
+    pub can_quit: bool,
     pub can_play: bool,
     pub can_pause: bool,
     pub can_go_next: bool,
     pub can_go_previous: bool,
+    pub can_seek: bool,
+    /// If false, the player cannot be controlled, and the properties below are read only
+    pub can_control: bool,
     pub playback_status: String,
+    pub rate: f64,
+    pub minimum_rate: f64,
+    pub maximum_rate: f64,
+    /// Shuffle, LoopStatus and Volume are optional, None if the player does not support them
+    pub shuffle: Option<bool>,
+    pub loop_status: Option<LoopStatus>,
+    pub volume: Option<f64>,
+    // NOTE: Position does not emit changed signals, so the last known one is kept with the
+    // time it is got, and the current one is interpolated from them
+    position: i64,
+    position_at: Instant,
     pub metadata: Metadata,
+    pub has_track_list: bool,
+    /// The tracks of the TrackList, the current one and the ones up next
+    pub tracks: Vec<Metadata>,
+    /// None if the player does not support the Playlists interface
+    pub playlists: Option<Vec<Playlist>>,
+    pub active_playlist: Option<String>,
 }
 
 impl ServiceInfo {
-    fn new(
-        path: &str,
-        can_play: bool,
-        can_pause: bool,
-        can_go_previous: bool,
-        can_go_next: bool,
-        playback_status: String,
-        value: HashMap<String, OwnedValue>,
-    ) -> Self {
-        Self {
+    async fn from_proxy(path: &str, instance: &MediaPlayer2DbusProxy<'_>) -> Result<Self> {
+        let conn = instance.inner().connection();
+        let root = MediaPlayer2RootDbusProxy::builder(conn)
+            .destination(path)?
+            .build()
+            .await?;
+        let tracklist = TrackListDbusProxy::builder(conn)
+            .destination(path)?
+            .build()
+            .await?;
+        let playlists_proxy = PlaylistsDbusProxy::builder(conn)
+            .destination(path)?
+            .build()
+            .await?;
+        // NOTE: the properties are read at the same time, only Metadata and PlaybackStatus must
+        // be read, the player is degraded if the other ones in the spec cannot be
+        let (
+            (value, playback_status, can_play, can_pause, can_go_next, can_go_previous),
+            (can_seek, can_control, rate, position, minimum_rate, maximum_rate),
+            (shuffle, loop_status, volume),
+            (identity, can_raise, can_quit, has_track_list),
+        ) = tokio::join!(
+            async {
+                tokio::join!(
+                    instance.metadata(),
+                    instance.playback_status(),
+                    instance.can_play(),
+                    instance.can_pause(),
+                    instance.can_go_next(),
+                    instance.can_go_previous(),
+                )
+            },
+            async {
+                tokio::join!(
+                    instance.can_seek(),
+                    instance.can_control(),
+                    instance.rate(),
+                    instance.position(),
+                    instance.minimum_rate(),
+                    instance.maximum_rate(),
+                )
+            },
+            async {
+                tokio::join!(
+                    instance.shuffle(),
+                    instance.loop_status(),
+                    instance.volume()
+                )
+            },
+            async {
+                tokio::join!(
+                    root.identity(),
+                    root.can_raise(),
+                    root.can_quit(),
+                    root.has_track_list(),
+                )
+            },
+        );
+        let (value, playback_status) = (value?, playback_status?);
+        let degraded = [
+            can_play.is_err(),
+            can_pause.is_err(),
+            can_go_next.is_err(),
+            can_go_previous.is_err(),
+            can_seek.is_err(),
+            can_control.is_err(),
+            identity.is_err(),
+        ]
+        .contains(&true);
+        if degraded {
+            tracing::warn!("{path}: some properties cannot be read, the player is degraded");
+        }
+        let has_track_list = has_track_list.unwrap_or(false);
+        let (tracks, playlists) = tokio::join!(
+            async {
+                if has_track_list {
+                    fetch_tracks(&tracklist).await.unwrap_or_default()
+                } else {
+                    Vec::new()
+                }
+            },
+            fetch_playlists(&playlists_proxy),
+        );
+        let (playlists, active_playlist) = match playlists {
+            Ok((playlists, active)) => (Some(playlists), active),
+            Err(_) => (None, None),
+        };
+        let identity = identity.unwrap_or_else(|_| player_name(path).to_owned());
+        let can_play = can_play.unwrap_or(false);
+        let can_pause = can_pause.unwrap_or(false);
+        let can_go_next = can_go_next.unwrap_or(false);
+        let can_go_previous = can_go_previous.unwrap_or(false);
+        let can_seek = can_seek.unwrap_or(false);
+        let can_control = can_control.unwrap_or(false);
+        let can_raise = can_raise.unwrap_or(false);
+        let can_quit = can_quit.unwrap_or(false);
+        let rate = rate.unwrap_or(1.);
+        let position = position.unwrap_or(0);
+        let minimum_rate = minimum_rate.unwrap_or(1.);
+        let maximum_rate = maximum_rate.unwrap_or(1.);
+        let shuffle = shuffle.ok();
+        let loop_status = loop_status
+            .ok()
+            .and_then(|status| LoopStatus::from_property(&status));
+        let volume = volume.ok();
+        Ok(Self {
             service_path: path.to_owned(),
+            state: if degraded {
+                ConnectionState::Degraded
+            } else {
+                ConnectionState::Connected
+            },
+            identity,
+            can_raise,
+            can_quit,
             can_play,
             can_pause,
             can_go_previous,
             can_go_next,
+            can_seek,
+            can_control,
             playback_status,
+            rate,
+            minimum_rate,
+            maximum_rate,
+            shuffle,
+            loop_status,
+            volume,
+            position,
+            position_at: Instant::now(),
             metadata: Metadata::from_hashmap(value),
+            has_track_list,
+            tracks,
+            playlists,
+            active_playlist,
+        })
+    }
+
+    pub fn service_path(&self) -> &str {
+        &self.service_path
+    }
+
+    /// The player is the one the name in config points to, the name is matched with the
+    /// identity or the bus name, like `firefox` or `org.mpris.MediaPlayer2.firefox`
+    pub fn is_player(&self, name: &str) -> bool {
+        name.eq_ignore_ascii_case(&self.identity)
+            || name.eq_ignore_ascii_case(player_name(&self.service_path))
+            || name == self.service_path
+    }
+
+    /// The current position in microseconds, interpolated from the last one the player reported
+    pub fn position(&self) -> i64 {
+        if self.playback_status != "Playing" {
+            return self.position;
+        }
+        let elapsed = self.position_at.elapsed().as_micros() as f64 * self.rate;
+        let position = self.position + elapsed as i64;
+        match self.metadata.mpris_length {
+            Some(length) if length > 0 => position.min(length),
+            _ => position,
         }
     }
 
+    fn set_position_anchor(&mut self, position: i64) {
+        self.position = position;
+        self.position_at = Instant::now();
+    }
+
+    /// Move to the position in microseconds, by SetPosition, or by Seek if there is no track id
+    pub async fn set_position(&self, position: i64) -> Result<()> {
+        let conn = get_connection().await?;
+        let instance = MediaPlayer2DbusProxy::builder(&conn)
+            .destination(self.service_path.as_str())?
+            .build()
+            .await?;
+        if self.metadata.has_track() {
+            instance
+                .set_position(&self.metadata.mpris_trackid.as_ref(), position)
+                .await?;
+        } else {
+            instance.seek(position - self.position()).await?;
+        }
+        Ok(())
+    }
+
+    /// Seek forward, or backward if the offset in microseconds is negative
+    pub async fn seek(&self, offset: i64) -> Result<()> {
+        let conn = get_connection().await?;
+        let instance = MediaPlayer2DbusProxy::builder(&conn)
+            .destination(self.service_path.as_str())?
+            .build()
+            .await?;
+        instance.seek(offset).await?;
+        Ok(())
+    }
+
+    /// Bring the window of the player to the front
+    pub async fn raise(&self) -> Result<()> {
+        let conn = get_connection().await?;
+        let instance = MediaPlayer2RootDbusProxy::builder(&conn)
+            .destination(self.service_path.as_str())?
+            .build()
+            .await?;
+        instance.raise().await?;
+        Ok(())
+    }
+
+    pub async fn quit(&self) -> Result<()> {
+        let conn = get_connection().await?;
+        let instance = MediaPlayer2RootDbusProxy::builder(&conn)
+            .destination(self.service_path.as_str())?
+            .build()
+            .await?;
+        instance.quit().await?;
+        Ok(())
+    }
+
+    /// Skip to the track in the TrackList
+    pub async fn go_to(&self, track_id: &str) -> Result<()> {
+        let conn = get_connection().await?;
+        let instance = TrackListDbusProxy::builder(&conn)
+            .destination(self.service_path.as_str())?
+            .build()
+            .await?;
+        instance.go_to(&ObjectPath::try_from(track_id)?).await?;
+        Ok(())
+    }
+
+    pub async fn activate_playlist(&self, playlist_id: &str) -> Result<()> {
+        let conn = get_connection().await?;
+        let instance = PlaylistsDbusProxy::builder(&conn)
+            .destination(self.service_path.as_str())?
+            .build()
+            .await?;
+        instance
+            .activate_playlist(&ObjectPath::try_from(playlist_id)?)
+            .await?;
+        Ok(())
+    }
+
+    pub async fn set_shuffle(&self, shuffle: bool) -> Result<()> {
+        let conn = get_connection().await?;
+        let instance = MediaPlayer2DbusProxy::builder(&conn)
+            .destination(self.service_path.as_str())?
+            .build()
+            .await?;
+        instance.set_shuffle(shuffle).await?;
+        Ok(())
+    }
+
+    pub async fn set_loop_status(&self, status: LoopStatus) -> Result<()> {
+        let conn = get_connection().await?;
+        let instance = MediaPlayer2DbusProxy::builder(&conn)
+            .destination(self.service_path.as_str())?
+            .build()
+            .await?;
+        instance.set_loop_status(status.as_property()).await?;
+        Ok(())
+    }
+
+    /// Set the playback rate, it is clamped to the range the player supports
+    pub async fn set_rate(&self, rate: f64) -> Result<()> {
+        let conn = get_connection().await?;
+        let instance = MediaPlayer2DbusProxy::builder(&conn)
+            .destination(self.service_path.as_str())?
+            .build()
+            .await?;
+        instance
+            .set_rate(rate.clamp(self.minimum_rate, self.maximum_rate))
+            .await?;
+        Ok(())
+    }
+
+    /// Set the volume of the player, from 0.0 to 1.0
+    pub async fn set_volume(&self, volume: f64) -> Result<()> {
+        let conn = get_connection().await?;
+        let instance = MediaPlayer2DbusProxy::builder(&conn)
+            .destination(self.service_path.as_str())?
+            .build()
+            .await?;
+        instance.set_volume(volume.clamp(0., 1.)).await?;
+        Ok(())
+    }
+
     pub async fn pause(&self) -> Result<()> {
         let conn = get_connection().await?;
         let instance = MediaPlayer2DbusProxy::builder(&conn)
@@ -149,6 +736,52 @@ impl ServiceInfo {
     }
 }
 
+async fn fetch_tracks(tracklist: &TrackListDbusProxy<'_>) -> Result<Vec<Metadata>> {
+    let ids = tracklist.tracks().await?;
+    if ids.is_empty() {
+        return Ok(Vec::new());
+    }
+    let ids: Vec<ObjectPath<'_>> = ids.iter().map(|id| id.as_ref()).collect();
+    let tracks = tracklist.get_tracks_metadata(&ids).await?;
+    Ok(tracks.into_iter().map(Metadata::from_hashmap).collect())
+}
+
+/// The playlists in the first ordering the player supports, and the id of the active one
+async fn fetch_playlists(
+    playlists: &PlaylistsDbusProxy<'_>,
+) -> Result<(Vec<Playlist>, Option<String>)> {
+    let count = playlists.playlist_count().await?;
+    let orderings = playlists.orderings().await.unwrap_or_default();
+    let ordering = orderings
+        .first()
+        .map(String::as_str)
+        .unwrap_or("Alphabetical");
+    let list = playlists
+        .get_playlists(0, count.min(MAX_PLAYLISTS), ordering, false)
+        .await?
+        .into_iter()
+        .map(|(id, name, _icon)| Playlist { id, name })
+        .collect();
+    let active = playlists
+        .active_playlist()
+        .await
+        .ok()
+        .and_then(|(valid, (id, _, _))| valid.then(|| id.to_string()));
+    Ok((list, active))
+}
+
+/// The name of the player in the bus name, without the prefix and the instance suffix,
+/// `org.mpris.MediaPlayer2.firefox.instance_1_42` is `firefox`
+pub fn player_name(service_path: &str) -> &str {
+    let name = service_path
+        .strip_prefix(MPRIS_PREFIX)
+        .unwrap_or(service_path);
+    match name.split_once(".instance") {
+        Some((name, _)) => name,
+        None => name,
+    }
+}
+
 static SESSION: OnceLock<zbus::Connection> = OnceLock::new();
 
 async fn get_connection() -> zbus::Result<zbus::Connection> {
@@ -161,17 +794,91 @@ async fn get_connection() -> zbus::Result<zbus::Connection> {
     }
 }
 
+// NOTE: only the latest change matters, the receivers read MPIRS_CONNECTIONS themselves
+static MPRIS_CHANGED: LazyLock<tokio::sync::watch::Sender<()>> =
+    LazyLock::new(|| tokio::sync::watch::Sender::new(()));
+
+fn notify_changed() {
+    MPRIS_CHANGED.send_replace(());
+}
+
+/// Emits whenever a player appears, vanishes or changes its state
+pub fn listen_changes() -> iced::Subscription<()> {
+    iced::Subscription::run(|| {
+        iced::stream::channel(
+            10,
+            |mut output: futures::channel::mpsc::Sender<()>| async move {
+                use iced::futures::sink::SinkExt;
+                let mut receiver = MPRIS_CHANGED.subscribe();
+                while receiver.changed().await.is_ok() {
+                    if output.send(()).await.is_err() {
+                        break;
+                    }
+                }
+            },
+        )
+    })
+}
+
 pub static MPIRS_CONNECTIONS: LazyLock<Arc<Mutex<Vec<ServiceInfo>>>> =
     LazyLock::new(|| Arc::new(Mutex::new(Vec::new())));
 
+type WatchHandle = tokio::task::JoinHandle<()>;
+
 #[allow(clippy::type_complexity)]
-pub static ZBUS_HANDLES: LazyLock<
-    Arc<
-        Mutex<
-            HashMap<String, Vec<tokio::task::JoinHandle<std::result::Result<(), anyhow::Error>>>>,
-        >,
-    >,
-> = LazyLock::new(|| Arc::new(Mutex::new(HashMap::new())));
+pub static ZBUS_HANDLES: LazyLock<Arc<Mutex<HashMap<String, Vec<WatchHandle>>>>> =
+    LazyLock::new(|| Arc::new(Mutex::new(HashMap::new())));
+
+// NOTE: the players which are not in MPIRS_CONNECTIONS, because they cannot be connected
+static UNAVAILABLE_PLAYERS: LazyLock<Mutex<HashMap<String, ConnectionState>>> =
+    LazyLock::new(|| Mutex::new(HashMap::new()));
+
+/// The players which cannot be connected, and if they are tried again
+pub async fn unavailable_players() -> Vec<(String, ConnectionState)> {
+    let players = UNAVAILABLE_PLAYERS.lock().await;
+    let mut players: Vec<(String, ConnectionState)> = players
+        .iter()
+        .map(|(name, state)| (name.clone(), *state))
+        .collect();
+    players.sort_by(|(a, _), (b, _)| a.cmp(b));
+    players
+}
+
+async fn set_unavailable(service_path: &str, state: Option<ConnectionState>) {
+    let mut players = UNAVAILABLE_PLAYERS.lock().await;
+    match state {
+        Some(state) => players.insert(service_path.to_owned(), state),
+        None => players.remove(service_path),
+    };
+    drop(players);
+    notify_changed();
+}
+
+async fn mark_degraded(service_path: &str) {
+    let mut conns = MPIRS_CONNECTIONS.lock().await;
+    if let Some(info) = conns
+        .iter_mut()
+        .find(|info| info.service_path == service_path)
+        && info.state != ConnectionState::Degraded
+    {
+        info.state = ConnectionState::Degraded;
+        notify_changed();
+    }
+}
+
+// NOTE: a watcher ends when the player is removed, if it fails the player is marked degraded,
+// since its state is not followed any more
+fn spawn_watcher<F>(service_path: String, what: &'static str, watcher: F) -> WatchHandle
+where
+    F: Future<Output = anyhow::Result<()>> + Send + 'static,
+{
+    tokio::spawn(async move {
+        if let Err(e) = watcher.await {
+            tracing::warn!("{service_path}: stop watching {what}: {e}");
+            mark_degraded(&service_path).await;
+        }
+    })
+}
 
 async fn mpirs_is_ready_in<T: ToString>(path: T) -> bool {
     let conns = MPIRS_CONNECTIONS.lock().await;
@@ -180,115 +887,390 @@ async fn mpirs_is_ready_in<T: ToString>(path: T) -> bool {
         .any(|info| info.service_path == path.to_string())
 }
 
-async fn set_mpirs_connection(list: Vec<ServiceInfo>) -> Result<()> {
-    let mut conns = MPIRS_CONNECTIONS.lock().await;
-    for info in list.iter() {
-        connect_to_signal(info).await?;
-    }
-    *conns = list;
-    Ok(())
+// NOTE: load the art off the lock, it is only applied if the track still has the same art
+fn update_art(service_path: &str, metadata: &Metadata) {
+    let Some(url) = metadata.mpris_art_url.clone() else {
+        return;
+    };
+    let service_path = service_path.to_owned();
+    tokio::spawn(async move {
+        let image = crate::art::load_art(&url).await;
+        let mut conns = MPIRS_CONNECTIONS.lock().await;
+        if let Some(info) = conns.iter_mut().find(|info| {
+            info.service_path == service_path && info.metadata.mpris_art_url.as_ref() == Some(&url)
+        }) {
+            info.metadata.mpris_image = image;
+            info.metadata.art_pending = false;
+            notify_changed();
+        }
+    });
 }
 
-async fn add_mpirs_connection(mpirs_service_info: ServiceInfo) -> Result<()> {
+async fn add_mpirs_connection(mpirs_service_info: ServiceInfo) {
     let mut conns = MPIRS_CONNECTIONS.lock().await;
     conns.push(mpirs_service_info.clone());
     drop(conns);
-    connect_to_signal(&mpirs_service_info).await?;
+    notify_changed();
+    update_art(
+        &mpirs_service_info.service_path,
+        &mpirs_service_info.metadata,
+    );
+    // NOTE: the player is still shown if its signals cannot be watched, but its state may be stale
+    if let Err(e) = connect_to_signal(&mpirs_service_info).await {
+        tracing::warn!(
+            "{}: cannot watch the signals: {e}",
+            mpirs_service_info.service_path
+        );
+        mark_degraded(&mpirs_service_info.service_path).await;
+    }
+}
+
+/// The delay before the first retry, it is doubled after every failed one
+const RETRY_DELAY: Duration = Duration::from_secs(1);
+const MAX_RETRY_DELAY: Duration = Duration::from_secs(60);
+const MAX_RETRIES: u32 = 6;
+
+static RETRY_HANDLES: LazyLock<Mutex<HashMap<String, WatchHandle>>> =
+    LazyLock::new(|| Mutex::new(HashMap::new()));
+
+async fn try_connect(name: &str) -> anyhow::Result<()> {
+    let conn = get_connection().await?;
+    let instance = MediaPlayer2DbusProxy::builder(&conn)
+        .destination(name)?
+        .build()
+        .await?;
+    let info = ServiceInfo::from_proxy(name, &instance).await?;
+    if !mpirs_is_ready_in(name).await {
+        add_mpirs_connection(info).await;
+    }
+    set_unavailable(name, None).await;
     Ok(())
 }
 
+async fn has_owner(name: &str) -> bool {
+    let Ok(conn) = get_connection().await else {
+        return false;
+    };
+    let Ok(freedesktop) = DBusProxy::new(&conn).await else {
+        return false;
+    };
+    let Ok(bus_name) = name.try_into() else {
+        return false;
+    };
+    freedesktop.name_has_owner(bus_name).await.unwrap_or(false)
+}
+
+// NOTE: a player often registers its name before its properties can be read, so it is
+// tried again with a growing delay, until it works, it leaves the bus, or the retries run out
+async fn retry_connect(name: String) {
+    let mut delay = RETRY_DELAY;
+    for attempt in 1..=MAX_RETRIES {
+        set_unavailable(&name, Some(ConnectionState::Retrying { attempt })).await;
+        tokio::time::sleep(delay).await;
+        if !has_owner(&name).await {
+            set_unavailable(&name, None).await;
+            return;
+        }
+        match try_connect(&name).await {
+            Ok(()) => return,
+            Err(e) => tracing::warn!("{name}: attempt {attempt} to connect failed: {e}"),
+        }
+        delay = (delay * 2).min(MAX_RETRY_DELAY);
+    }
+    tracing::error!("{name}: cannot connect to the player, give up after {MAX_RETRIES} retries");
+    set_unavailable(&name, Some(ConnectionState::Gone)).await;
+}
+
+async fn connect_player(name: String) {
+    if let Some(handle) = RETRY_HANDLES.lock().await.remove(&name) {
+        handle.abort();
+    }
+    if let Err(e) = try_connect(&name).await {
+        tracing::warn!("{name}: cannot connect to the player: {e}");
+        let retry_name = name.clone();
+        // NOTE: the entry is only there while the player is retried, a finished retry removes it
+        let handle = tokio::spawn(async move {
+            retry_connect(retry_name.clone()).await;
+            RETRY_HANDLES.lock().await.remove(&retry_name);
+        });
+        RETRY_HANDLES.lock().await.insert(name, handle);
+    }
+}
+
+async fn forget_player(name: &str) {
+    if let Some(handle) = RETRY_HANDLES.lock().await.remove(name) {
+        handle.abort();
+    }
+    remove_mpirs_connection(name).await;
+    set_unavailable(name, None).await;
+}
+
 async fn connect_to_signal(mpirs_service_info: &ServiceInfo) -> Result<()> {
-    let service_path0 = mpirs_service_info.service_path.clone();
     let service_path = mpirs_service_info.service_path.clone();
-    let service_path2 = mpirs_service_info.service_path.clone();
-    let service_path3 = mpirs_service_info.service_path.clone();
-    let service_path4 = mpirs_service_info.service_path.clone();
     let conn = get_connection().await?;
     let instance = MediaPlayer2DbusProxy::builder(&conn)
         .destination(service_path.clone())?
         .build()
         .await?;
-    let mut statuschanged = instance.receive_playback_status_changed().await;
     let mut handle_pool = ZBUS_HANDLES.lock().await;
     if let Some(pre_handles) = handle_pool.get(&service_path) {
         for handle in pre_handles {
             handle.abort();
         }
     }
-    let status_handle = tokio::spawn(async move {
-        while let Some(signal) = statuschanged.next().await {
-            let status: String = signal.get().await?;
-            let mut conns = MPIRS_CONNECTIONS.lock().await;
-            if let Some(index) = conns
-                .iter()
-                .position(|info| info.service_path == service_path)
-            {
-                conns[index].playback_status = status;
-            } else {
-                break;
-            }
-        }
-        Ok::<(), anyhow::Error>(())
-    });
-    let mut metadatachanged = instance.receive_metadata_changed().await;
-    let metadata_handle = tokio::spawn(async move {
-        while let Some(signal) = metadatachanged.next().await {
-            let metadatamap = signal.get().await?;
-            let metadata = Metadata::from_hashmap(metadatamap);
-            let mut conns = MPIRS_CONNECTIONS.lock().await;
-            if let Some(index) = conns
-                .iter()
-                .position(|info| info.service_path == service_path2)
-            {
-                conns[index].metadata = metadata;
-            } else {
-                break;
-            }
-        }
-        Ok::<(), anyhow::Error>(())
-    });
-    let mut can_go_next_changed = instance.receive_can_go_next_changed().await;
-    let go_next_handle = tokio::spawn(async move {
-        while let Some(signal) = can_go_next_changed.next().await {
-            let can_go_next = signal.get().await?;
-            let mut conns = MPIRS_CONNECTIONS.lock().await;
-            if let Some(index) = conns
-                .iter()
-                .position(|info| info.service_path == service_path3)
-            {
-                conns[index].can_go_next = can_go_next;
-            } else {
-                break;
-            }
-        }
-        Ok::<(), anyhow::Error>(())
-    });
-    let mut can_go_pre_changed = instance.receive_can_go_previous_changed().await;
-    let go_pre_handle = tokio::spawn(async move {
-        while let Some(signal) = can_go_pre_changed.next().await {
-            let can_go_pre = signal.get().await?;
+    let status_instance = instance.clone();
+    let metadata_instance = instance.clone();
+    let player_handles = [
+        watch_signals(
+            service_path.clone(),
+            "PlaybackStatus",
+            instance.receive_playback_status_changed().await,
+            move |signal| {
+                let instance = status_instance.clone();
+                async move {
+                    let status: String = signal.get().await?;
+                    Ok((status, instance.position().await.ok()))
+                }
+            },
+            |info, (status, position)| {
+                let position = position.unwrap_or(info.position());
+                info.set_position_anchor(position);
+                info.playback_status = status;
+            },
+        ),
+        watch_signals(
+            service_path.clone(),
+            "Metadata",
+            instance.receive_metadata_changed().await,
+            move |signal| {
+                let instance = metadata_instance.clone();
+                async move {
+                    let metadata = Metadata::from_hashmap(signal.get().await?);
+                    Ok((metadata, instance.position().await.unwrap_or(0)))
+                }
+            },
+            |info, (mut metadata, position)| {
+                if info.metadata.mpris_trackid != metadata.mpris_trackid {
+                    info.set_position_anchor(position);
+                }
+                // NOTE: the old art is shown until the new one is loaded
+                if metadata.mpris_art_url.is_some() {
+                    metadata.mpris_image = info.metadata.mpris_image.clone();
+                }
+                // NOTE: a player may rewrite the art of the next track to the same file
+                if metadata.mpris_art_url == info.metadata.mpris_art_url
+                    && metadata.mpris_trackid == info.metadata.mpris_trackid
+                {
+                    metadata.art_pending = info.metadata.art_pending;
+                } else {
+                    update_art(&info.service_path, &metadata);
+                }
+                info.metadata = metadata;
+            },
+        ),
+        watch_property(
+            service_path.clone(),
+            "CanGoNext",
+            instance.receive_can_go_next_changed().await,
+            |info, can_go_next| info.can_go_next = can_go_next,
+        ),
+        watch_property(
+            service_path.clone(),
+            "CanGoPrevious",
+            instance.receive_can_go_previous_changed().await,
+            |info, can_go_previous| info.can_go_previous = can_go_previous,
+        ),
+        watch_signals(
+            service_path.clone(),
+            "Seeked",
+            instance.receive_seeked().await?,
+            |signal| async move { Ok(signal.args()?.position) },
+            |info, position| info.set_position_anchor(position),
+        ),
+        watch_property(
+            service_path.clone(),
+            "Rate",
+            instance.receive_rate_changed().await,
+            |info, rate| {
+                // NOTE: the position before the change is interpolated with the old rate
+                let position = info.position();
+                info.set_position_anchor(position);
+                info.rate = rate;
+            },
+        ),
+    ];
+    let mut list_handles = Vec::new();
+    if mpirs_service_info.has_track_list {
+        let tracklist = TrackListDbusProxy::builder(&conn)
+            .destination(service_path.clone())?
+            .cache_properties(zbus::proxy::CacheProperties::No)
+            .build()
+            .await?;
+        let changes = futures_util::stream::select_all([
+            tracklist
+                .receive_track_list_replaced()
+                .await?
+                .map(|_| ())
+                .boxed(),
+            tracklist.receive_track_added().await?.map(|_| ()).boxed(),
+            tracklist.receive_track_removed().await?.map(|_| ()).boxed(),
+            tracklist
+                .receive_track_metadata_changed()
+                .await?
+                .map(|_| ())
+                .boxed(),
+        ])
+        .boxed();
+        list_handles.push(refresh_on(
+            service_path.clone(),
+            changes,
+            move || {
+                let tracklist = tracklist.clone();
+                async move { fetch_tracks(&tracklist).await }
+            },
+            |info, tracks| info.tracks = tracks,
+        ));
+    }
+    if mpirs_service_info.playlists.is_some() {
+        let playlists = PlaylistsDbusProxy::builder(&conn)
+            .destination(service_path.clone())?
+            .cache_properties(zbus::proxy::CacheProperties::No)
+            .build()
+            .await?;
+        let changes = playlists
+            .receive_playlist_changed()
+            .await?
+            .map(|_| ())
+            .boxed();
+        list_handles.push(refresh_on(
+            service_path.clone(),
+            changes,
+            move || {
+                let playlists = playlists.clone();
+                async move { fetch_playlists(&playlists).await }
+            },
+            |info, (playlists, active)| {
+                info.playlists = Some(playlists);
+                info.active_playlist = active;
+            },
+        ));
+        // NOTE: the properties are only followed with the cache, the proxy above has none so the
+        // lists are always fetched again
+        let active = PlaylistsDbusProxy::builder(&conn)
+            .destination(service_path.clone())?
+            .build()
+            .await?;
+        list_handles.push(watch_property(
+            service_path.clone(),
+            "ActivePlaylist",
+            active.receive_active_playlist_changed().await,
+            |info, (valid, (id, _, _)): (bool, (OwnedObjectPath, String, String))| {
+                info.active_playlist = valid.then(|| id.to_string())
+            },
+        ));
+    }
+    let control_handles = [
+        watch_property(
+            service_path.clone(),
+            "CanControl",
+            instance.receive_can_control_changed().await,
+            |info, can_control| info.can_control = can_control,
+        ),
+        watch_property(
+            service_path.clone(),
+            "Shuffle",
+            instance.receive_shuffle_changed().await,
+            |info, shuffle| info.shuffle = Some(shuffle),
+        ),
+        watch_property(
+            service_path.clone(),
+            "LoopStatus",
+            instance.receive_loop_status_changed().await,
+            |info, status: String| info.loop_status = LoopStatus::from_property(&status),
+        ),
+        watch_property(
+            service_path.clone(),
+            "Volume",
+            instance.receive_volume_changed().await,
+            |info, volume| info.volume = Some(volume),
+        ),
+    ];
+    handle_pool.insert(
+        service_path,
+        player_handles
+            .into_iter()
+            .chain(control_handles)
+            .chain(list_handles)
+            .collect(),
+    );
+    Ok(())
+}
+
+// NOTE: apply what is read from every signal to the player, until the player is removed
+fn watch_signals<S, T, F, Fut>(
+    service_path: String,
+    what: &'static str,
+    mut signals: S,
+    read: F,
+    apply: fn(&mut ServiceInfo, T),
+) -> WatchHandle
+where
+    S: futures_util::Stream + Unpin + Send + 'static,
+    S::Item: Send,
+    T: Send + 'static,
+    F: Fn(S::Item) -> Fut + Send + 'static,
+    Fut: Future<Output = Result<T>> + Send,
+{
+    spawn_watcher(service_path.clone(), what, async move {
+        while let Some(signal) = signals.next().await {
+            let value = read(signal).await?;
             let mut conns = MPIRS_CONNECTIONS.lock().await;
-            if let Some(index) = conns
-                .iter()
-                .position(|info| info.service_path == service_path4.clone())
-            {
-                conns[index].can_go_previous = can_go_pre;
-            } else {
+            let Some(info) = conns
+                .iter_mut()
+                .find(|info| info.service_path == service_path)
+            else {
                 break;
-            }
+            };
+            apply(info, value);
+            notify_changed();
         }
         Ok::<(), anyhow::Error>(())
-    });
-    handle_pool.insert(
-        service_path0,
-        vec![
-            status_handle,
-            metadata_handle,
-            go_next_handle,
-            go_pre_handle,
-        ],
-    );
-    Ok(())
+    })
+}
+
+// NOTE: keep one property of the player in sync
+fn watch_property<T>(
+    service_path: String,
+    what: &'static str,
+    changed: PropertyStream<'static, T>,
+    apply: fn(&mut ServiceInfo, T),
+) -> WatchHandle
+where
+    T: TryFrom<OwnedValue> + Unpin + Send + Sync + 'static,
+    T::Error: Into<zbus::Error>,
+{
+    watch_signals(
+        service_path,
+        what,
+        changed,
+        |signal| async move { signal.get().await },
+        apply,
+    )
+}
+
+// NOTE: fetch a part of the player state again on every signal
+fn refresh_on<T, F, Fut>(
+    service_path: String,
+    changes: futures_util::stream::BoxStream<'static, ()>,
+    fetch: F,
+    apply: fn(&mut ServiceInfo, T),
+) -> WatchHandle
+where
+    T: Send + 'static,
+    F: Fn() -> Fut + Send + 'static,
+    Fut: Future<Output = Result<T>> + Send,
+{
+    watch_signals(service_path, "lists", changes, move |()| fetch(), apply)
 }
 
 async fn remove_mpirs_connection(conn: &str) {
@@ -300,6 +1282,97 @@ async fn remove_mpirs_connection(conn: &str) {
     }
     let mut conns = MPIRS_CONNECTIONS.lock().await;
     conns.retain(|iter| iter.service_path != conn);
+    notify_changed();
+}
+
+#[proxy(
+    interface = "org.mpris.MediaPlayer2",
+    default_path = "/org/mpris/MediaPlayer2"
+)]
+trait MediaPlayer2RootDbus {
+    #[zbus(property)]
+    fn identity(&self) -> Result<String>;
+
+    #[zbus(property)]
+    fn can_raise(&self) -> Result<bool>;
+
+    #[zbus(property)]
+    fn can_quit(&self) -> Result<bool>;
+
+    #[zbus(property)]
+    fn has_track_list(&self) -> Result<bool>;
+
+    fn raise(&self) -> Result<()>;
+
+    fn quit(&self) -> Result<()>;
+}
+
+#[proxy(
+    interface = "org.mpris.MediaPlayer2.TrackList",
+    default_path = "/org/mpris/MediaPlayer2"
+)]
+trait TrackListDbus {
+    #[zbus(property(emits_changed_signal = "invalidates"))]
+    fn tracks(&self) -> Result<Vec<OwnedObjectPath>>;
+
+    fn get_tracks_metadata(
+        &self,
+        track_ids: &[ObjectPath<'_>],
+    ) -> Result<Vec<HashMap<String, OwnedValue>>>;
+
+    fn go_to(&self, track_id: &ObjectPath<'_>) -> Result<()>;
+
+    #[zbus(signal)]
+    fn track_list_replaced(
+        &self,
+        tracks: Vec<OwnedObjectPath>,
+        current_track: OwnedObjectPath,
+    ) -> Result<()>;
+
+    #[zbus(signal)]
+    fn track_added(
+        &self,
+        metadata: HashMap<String, OwnedValue>,
+        after_track: OwnedObjectPath,
+    ) -> Result<()>;
+
+    #[zbus(signal)]
+    fn track_removed(&self, track_id: OwnedObjectPath) -> Result<()>;
+
+    #[zbus(signal)]
+    fn track_metadata_changed(
+        &self,
+        track_id: OwnedObjectPath,
+        metadata: HashMap<String, OwnedValue>,
+    ) -> Result<()>;
+}
+
+#[proxy(
+    interface = "org.mpris.MediaPlayer2.Playlists",
+    default_path = "/org/mpris/MediaPlayer2"
+)]
+trait PlaylistsDbus {
+    #[zbus(property)]
+    fn playlist_count(&self) -> Result<u32>;
+
+    #[zbus(property)]
+    fn orderings(&self) -> Result<Vec<String>>;
+
+    #[zbus(property)]
+    fn active_playlist(&self) -> Result<(bool, (OwnedObjectPath, String, String))>;
+
+    fn get_playlists(
+        &self,
+        index: u32,
+        max_count: u32,
+        order: &str,
+        reverse_order: bool,
+    ) -> Result<Vec<(OwnedObjectPath, String, String)>>;
+
+    fn activate_playlist(&self, playlist_id: &ObjectPath<'_>) -> Result<()>;
+
+    #[zbus(signal)]
+    fn playlist_changed(&self, playlist: (OwnedObjectPath, String, String)) -> Result<()>;
 }
 
 #[proxy(
@@ -325,6 +1398,45 @@ trait MediaPlayer2Dbus {
     #[zbus(property)]
     fn metadata(&self) -> Result<HashMap<String, OwnedValue>>;
 
+    #[zbus(property)]
+    fn can_seek(&self) -> Result<bool>;
+
+    #[zbus(property)]
+    fn can_control(&self) -> Result<bool>;
+
+    #[zbus(property)]
+    fn rate(&self) -> Result<f64>;
+
+    #[zbus(property)]
+    fn set_rate(&self, rate: f64) -> Result<()>;
+
+    #[zbus(property)]
+    fn minimum_rate(&self) -> Result<f64>;
+
+    #[zbus(property)]
+    fn maximum_rate(&self) -> Result<f64>;
+
+    #[zbus(property)]
+    fn shuffle(&self) -> Result<bool>;
+
+    #[zbus(property)]
+    fn set_shuffle(&self, shuffle: bool) -> Result<()>;
+
+    #[zbus(property)]
+    fn loop_status(&self) -> Result<String>;
+
+    #[zbus(property)]
+    fn set_loop_status(&self, status: &str) -> Result<()>;
+
+    #[zbus(property)]
+    fn volume(&self) -> Result<f64>;
+
+    #[zbus(property)]
+    fn set_volume(&self, volume: f64) -> Result<()>;
+
+    #[zbus(property(emits_changed_signal = "false"))]
+    fn position(&self) -> Result<i64>;
+
     fn pause(&self) -> Result<()>;
 
     fn play(&self) -> Result<()>;
@@ -332,6 +1444,13 @@ trait MediaPlayer2Dbus {
     fn next(&self) -> Result<()>;
 
     fn previous(&self) -> Result<()>;
+
+    fn seek(&self, offset: i64) -> Result<()>;
+
+    fn set_position(&self, track_id: &ObjectPath<'_>, position: i64) -> Result<()>;
+
+    #[zbus(signal)]
+    fn seeked(&self, position: i64) -> Result<()>;
 }
 
 pub async fn init_mpirs() -> Result<()> {
@@ -344,31 +1463,10 @@ pub async fn init_mpirs() -> Result<()> {
         .map(|name| name.to_string())
         .collect();
 
-    let mut serviceinfos = Vec::new();
-    for name in names.iter() {
-        let instance = MediaPlayer2DbusProxy::builder(&conn)
-            .destination(name.as_str())?
-            .build()
-            .await?;
-
-        let value = instance.metadata().await?;
-        let can_pause = instance.can_pause().await?;
-        let can_play = instance.can_play().await?;
-        let playback_status = instance.playback_status().await?;
-        let can_go_next = instance.can_go_next().await?;
-        let can_go_previous = instance.can_go_previous().await?;
-        serviceinfos.push(ServiceInfo::new(
-            name,
-            can_play,
-            can_pause,
-            can_go_next,
-            can_go_previous,
-            playback_status,
-            value,
-        ));
+    // NOTE: one slow or broken player must not stop the others from being shown
+    for name in names {
+        tokio::spawn(connect_player(name));
     }
-
-    set_mpirs_connection(serviceinfos).await.ok();
     tokio::spawn(async move {
         let mut namechangesignal = freedesktop.receive_name_owner_changed().await?;
         while let Some(signal) = namechangesignal.next().await {
@@ -379,37 +1477,65 @@ pub async fn init_mpirs() -> Result<()> {
                 ..
             } = signal.args()?;
 
-            if !interfacename.starts_with("org.mpris.MediaPlayer2") && interfacename != PLAYCTLD {
+            if !interfacename.starts_with(MPRIS_PREFIX) || interfacename == PLAYCTLD {
                 continue;
             }
+            // NOTE: the owner may be replaced, so the old one is removed before adding the new one
             if old_owner.is_some() {
-                remove_mpirs_connection(interfacename.as_str()).await;
-            } else if new_owner.is_some() && !mpirs_is_ready_in(interfacename.as_str()).await {
-                let instance = MediaPlayer2DbusProxy::builder(&conn)
-                    .destination(interfacename.as_str())?
-                    .build()
-                    .await?;
-
-                let value = instance.metadata().await?;
-                let can_pause = instance.can_pause().await?;
-                let can_play = instance.can_play().await?;
-                let can_go_next = instance.can_go_next().await?;
-                let can_go_previous = instance.can_go_previous().await?;
-                let playback_status = instance.playback_status().await?;
-                add_mpirs_connection(ServiceInfo::new(
-                    interfacename.as_str(),
-                    can_play,
-                    can_pause,
-                    can_go_next,
-                    can_go_previous,
-                    playback_status,
-                    value,
-                ))
-                .await
-                .ok();
+                forget_player(interfacename.as_str()).await;
+            }
+            if new_owner.is_some() && !mpirs_is_ready_in(interfacename.as_str()).await {
+                tokio::spawn(connect_player(interfacename.to_string()));
             }
         }
         Ok::<(), anyhow::Error>(())
     });
     Ok(())
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn metadata(fields: Vec<(&str, Value<'_>)>) -> Metadata {
+        Metadata::from_hashmap(
+            fields
+                .into_iter()
+                .map(|(key, value)| (key.to_owned(), value.try_to_owned().unwrap()))
+                .collect(),
+        )
+    }
+
+    #[test]
+    fn wrong_variant_types() {
+        let data = metadata(vec![
+            ("xesam:title", Value::from("song")),
+            ("xesam:artist", Value::from("someone")),
+            ("xesam:genre", Value::from(vec!["rock", "pop"])),
+            ("xesam:trackNumber", Value::from("7")),
+            ("mpris:length", Value::from(180_000_000_u64)),
+            ("mpris:trackid", Value::from("/org/track/1")),
+            ("xesam:userRating", Value::from(1_i32)),
+            ("xesam:discNumber", Value::from(true)),
+        ]);
+        assert_eq!(data.artists(), ["someone"]);
+        assert_eq!(data.genres(), ["rock", "pop"]);
+        assert_eq!(data.track_number(), Some(7));
+        assert_eq!(data.mpris_length, Some(180_000_000));
+        assert_eq!(data.track_id(), "/org/track/1");
+        assert_eq!(data.user_rating(), Some(1.));
+        assert_eq!(data.disc_number(), None);
+    }
+
+    #[test]
+    fn format_with_sections() {
+        let data = metadata(vec![
+            ("xesam:title", Value::from("song")),
+            ("xesam:artist", Value::from(vec!["a", "b"])),
+        ]);
+        assert_eq!(data.format("{title}[ — {artist}]"), "song — a, b");
+        assert_eq!(data.format("{artist} - {title}[ ({album})]"), "a, b - song");
+        assert_eq!(data.format("{title}[ #{track_number}"), "song");
+        assert_eq!(data.format("{unknown}{title}"), "song");
+    }
+}